@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// Picks the endpoint of host_ports to connect to. Stays on the endpoint while its connections
+// get stable and moves to the next one only after a connection which failed or dropped too early.
+// Stable connection resets the rotation back to the first endpoint
+pub struct EndpointRotation {
+    index: AtomicUsize,
+    attempt_made: AtomicBool,
+    stable: AtomicBool,
+}
+
+impl EndpointRotation {
+    pub fn new() -> Self {
+        Self {
+            index: AtomicUsize::new(0),
+            attempt_made: AtomicBool::new(false),
+            stable: AtomicBool::new(false),
+        }
+    }
+
+    pub fn connection_is_stable(&self) {
+        self.index.store(0, Ordering::SeqCst);
+        self.stable.store(true, Ordering::SeqCst);
+    }
+
+    pub fn next_index(&self, endpoints_count: usize) -> usize {
+        let stable = self.stable.swap(false, Ordering::SeqCst);
+
+        if self.attempt_made.swap(true, Ordering::SeqCst) && !stable {
+            self.index.fetch_add(1, Ordering::SeqCst);
+        }
+
+        self.index.load(Ordering::SeqCst) % endpoints_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EndpointRotation;
+
+    #[test]
+    fn test_advances_only_on_failure_and_resets_on_stable_connection() {
+        let rotation = EndpointRotation::new();
+
+        assert_eq!(0, rotation.next_index(3));
+        assert_eq!(1, rotation.next_index(3));
+        assert_eq!(2, rotation.next_index(3));
+        assert_eq!(0, rotation.next_index(3));
+        assert_eq!(1, rotation.next_index(3));
+
+        rotation.connection_is_stable();
+        assert_eq!(0, rotation.next_index(3));
+        assert_eq!(1, rotation.next_index(3));
+
+        rotation.connection_is_stable();
+        assert_eq!(0, rotation.next_index(3));
+    }
+}
@@ -1,11 +1,26 @@
+mod endpoint_rotation;
+mod my_no_sql_reader_error;
 mod my_no_sql_tcp_connection;
+mod my_no_sql_tcp_connection_builder;
+mod reconnect_backoff;
 mod settings;
 mod subscribers;
 mod tcp_events;
 
+pub use my_no_sql_reader_error::*;
 pub use my_no_sql_tcp_connection::MyNoSqlTcpConnection;
+pub use my_no_sql_tcp_connection_builder::MyNoSqlTcpConnectionBuilder;
+pub use reconnect_backoff::ReconnectBackoff;
 pub use settings::*;
-pub use subscribers::{MyNoSqlDataReaderCallBacks, MyNoSqlDataReaderData, MyNoSqlDataReaderTcp};
+pub use subscribers::{
+    BrokenEntitiesHandler, BrokenEntity, CallbackError, CallbackEvent, CallbackResult,
+    CallbacksDeadLetter, CallbacksDeadLetterHandler, CallbacksOverflowPolicy,
+    CallbacksQueueMetrics, CallbacksRetryPolicy, MyNoSqlDataReaderCallBacks, MyNoSqlDataReaderData,
+    MyNoSqlDataReaderExtendedCallBacks, MyNoSqlDataReaderStatus, MyNoSqlDataReaderSync,
+    MyNoSqlDataReaderTcp, MyNoSqlDataReaderView, MyNoSqlPartitionSnapshot, MyNoSqlReadView,
+    MyNoSqlTableEntities, MyNoSqlTableSnapshot, ReaderChange, RemoveReaderResult,
+    TableNotFoundPolicy, UnchangedRowsPolicy,
+};
 
 #[cfg(feature = "mocks")]
 pub use subscribers::MyNoSqlDataReaderMock;
@@ -0,0 +1,76 @@
+#[derive(Debug, Clone)]
+pub enum MyNoSqlReaderError {
+    ServerError { message: String },
+    CompressedPayload { message: String },
+    TableNotFound { table_name: String },
+}
+
+impl MyNoSqlReaderError {
+    pub fn get_message(&self) -> String {
+        match self {
+            MyNoSqlReaderError::ServerError { message } => format!("Server error: {}", message),
+            MyNoSqlReaderError::CompressedPayload { message } => {
+                format!("Can not decompress payload: {}", message)
+            }
+            MyNoSqlReaderError::TableNotFound { table_name } => {
+                format!("Table {} is not found", table_name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WaitError {
+    Timeout { table_names: Vec<String> },
+    TableNotFound { table_name: String },
+}
+
+impl WaitError {
+    pub fn get_message(&self) -> String {
+        match self {
+            WaitError::Timeout { table_names } => format!(
+                "Timeout waiting for the first data of tables: {}",
+                table_names.join(", ")
+            ),
+            WaitError::TableNotFound { table_name } => {
+                format!("Table {} is not found", table_name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum GetReaderError {
+    // Table is already read by the connection with another entity type
+    EntityTypeMismatch {
+        table_name: String,
+        registered_type: &'static str,
+        requested_type: &'static str,
+    },
+}
+
+impl GetReaderError {
+    pub fn get_message(&self) -> String {
+        match self {
+            GetReaderError::EntityTypeMismatch {
+                table_name,
+                registered_type,
+                requested_type,
+            } => format!(
+                "Table {} is already subscribed with entity type {}. Can not subscribe it with entity type {}",
+                table_name, registered_type, requested_type
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServerErrorPolicy {
+    // Write error to the log and drop the connection so TcpClient reconnects
+    #[default]
+    LogAndReconnect,
+    // Only publish error to the errors watch channel. Connection stays alive
+    Propagate,
+    // Panic the same way the reader did before policies were introduced
+    Abort,
+}
@@ -1,29 +1,118 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use my_no_sql_tcp_shared::{sync_to_main::SyncToMainNodeHandler, MyNoSqlReaderTcpSerializer};
 use my_tcp_sockets::TcpClient;
 use rust_extensions::{AppStates, Logger, StrOrString};
 use serde::de::DeserializeOwned;
+use tokio::sync::watch;
 
 use crate::{
-    subscribers::MyNoSqlDataReaderTcp, tcp_events::TcpEvents, MyNoSqlTcpConnectionSettings,
+    subscribers::{MyNoSqlDataReaderTcp, RemoveReaderResult, UpdateEvent},
+    tcp_events::TcpEvents,
+    GetReaderError, MyNoSqlReaderError, MyNoSqlTcpConnectionBuilder, MyNoSqlTcpConnectionSettings,
+    ReconnectBackoff, ServerErrorPolicy, WaitError,
 };
 
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// Delay between connect attempts when no ReconnectBackoff is set
+const DEFAULT_RECONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
 pub struct TcpConnectionSettings {
     settings: Arc<dyn MyNoSqlTcpConnectionSettings + Sync + Send + 'static>,
+    last_host_port: Mutex<Option<String>>,
+    reconnect_backoff: Option<ReconnectBackoff>,
+    reconnect_timeout: Duration,
+    connect_timeout: Mutex<Duration>,
+    tcp_events: Arc<TcpEvents>,
+}
+
+impl TcpConnectionSettings {
+    pub fn get_last_host_port(&self) -> Option<String> {
+        self.last_host_port.lock().unwrap().clone()
+    }
+}
+
+impl TcpConnectionSettings {
+    async fn pick_host_port(&self) -> String {
+        let reconnect_no = self.tcp_events.next_connect_attempt();
+
+        if let (Some(reconnect_backoff), Some(reconnect_no)) =
+            (self.reconnect_backoff.as_ref(), reconnect_no)
+        {
+            let delay = reconnect_backoff.get_delay(reconnect_no);
+            if delay.as_millis() > 0 {
+                tokio::time::sleep(delay).await;
+            }
+        }
+
+        let host_ports = self.settings.get_host_ports().await;
+
+        let host_port = if host_ports.is_empty() {
+            self.settings.get_host_port().await
+        } else {
+            let index = self
+                .tcp_events
+                .endpoint_rotation
+                .next_index(host_ports.len());
+            host_ports[index].to_string()
+        };
+
+        *self.last_host_port.lock().unwrap() = Some(host_port.to_string());
+
+        host_port
+    }
 }
 
 #[async_trait::async_trait]
 impl my_tcp_sockets::TcpClientSocketSettings for TcpConnectionSettings {
+    // TcpClient connects without a timeout, so the endpoint is probed with connect_timeout first.
+    // Endpoint which does not accept the probe in time counts as a failed connect attempt
     async fn get_host_port(&self) -> String {
-        self.settings.get_host_port().await
+        loop {
+            let host_port = self.pick_host_port().await;
+            let connect_timeout = *self.connect_timeout.lock().unwrap();
+
+            let probe = tokio::net::TcpStream::connect(host_port.as_str());
+
+            match tokio::time::timeout(connect_timeout, probe).await {
+                Ok(Ok(_)) => return host_port,
+                Ok(Err(err)) => {
+                    my_logger::LOGGER.write_error(
+                        "MyNoSqlTcpReader".to_string(),
+                        format!("Can not connect to {}: {}", host_port, err),
+                        None,
+                    );
+                }
+                Err(_) => {
+                    my_logger::LOGGER.write_error(
+                        "MyNoSqlTcpReader".to_string(),
+                        format!(
+                            "Connect to {} timed out after {:?}",
+                            host_port, connect_timeout
+                        ),
+                        None,
+                    );
+                }
+            }
+
+            if self.reconnect_backoff.is_none() {
+                tokio::time::sleep(self.reconnect_timeout).await;
+            }
+        }
     }
 }
 
 pub struct MyNoSqlTcpConnection {
     tcp_client: TcpClient,
+    settings: Arc<TcpConnectionSettings>,
+    #[deprecated(note = "Not applied to the connection. Use MyNoSqlTcpConnection::builder")]
     pub ping_timeout: Duration,
+    // Applied when the connection starts
+    #[deprecated(note = "Use MyNoSqlTcpConnectionBuilder::set_connect_timeout")]
     pub connect_timeout: Duration,
     pub tcp_events: Arc<TcpEvents>,
     app_states: Arc<AppStates>,
@@ -34,18 +123,69 @@ impl MyNoSqlTcpConnection {
         app_name: impl Into<StrOrString<'static>>,
         settings: Arc<dyn MyNoSqlTcpConnectionSettings + Sync + Send + 'static>,
     ) -> Self {
-        let settings = TcpConnectionSettings { settings };
+        Self::new_with_error_policy(app_name, settings, ServerErrorPolicy::default())
+    }
+
+    pub fn new_with_error_policy(
+        app_name: impl Into<StrOrString<'static>>,
+        settings: Arc<dyn MyNoSqlTcpConnectionSettings + Sync + Send + 'static>,
+        server_error_policy: ServerErrorPolicy,
+    ) -> Self {
+        MyNoSqlTcpConnectionBuilder::new(app_name, settings)
+            .set_server_error_policy(server_error_policy)
+            .build()
+    }
+
+    pub fn builder(
+        app_name: impl Into<StrOrString<'static>>,
+        settings: Arc<dyn MyNoSqlTcpConnectionSettings + Sync + Send + 'static>,
+    ) -> MyNoSqlTcpConnectionBuilder {
+        MyNoSqlTcpConnectionBuilder::new(app_name, settings)
+    }
+
+    #[allow(deprecated)]
+    pub(crate) fn from_builder(builder: MyNoSqlTcpConnectionBuilder) -> Self {
+        let tcp_events = Arc::new(TcpEvents::new(
+            builder.app_name.to_string(),
+            Arc::new(SyncToMainNodeHandler::new()),
+            builder.server_error_policy,
+        ));
 
-        let app_name: StrOrString<'static> = app_name.into();
+        let connect_timeout = builder.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+
+        let settings = Arc::new(TcpConnectionSettings {
+            settings: builder.settings,
+            last_host_port: Mutex::new(None),
+            reconnect_backoff: builder.reconnect_backoff,
+            reconnect_timeout: builder
+                .reconnect_timeout
+                .unwrap_or(DEFAULT_RECONNECT_TIMEOUT),
+            connect_timeout: Mutex::new(connect_timeout),
+            tcp_events: tcp_events.clone(),
+        });
+
+        let mut tcp_client = TcpClient::new("MyNoSqlClient".to_string(), settings.clone());
+
+        if let Some(ping_interval) = builder.ping_interval {
+            // TcpClient pings with a second resolution: round up so sub-second values do not turn into 0
+            let seconds_to_ping = (ping_interval.as_millis() + 999) / 1000;
+            tcp_client = tcp_client.set_seconds_to_ping(seconds_to_ping.max(1) as usize);
+        }
+
+        if let Some(disconnect_timeout) = builder.disconnect_timeout {
+            tcp_client = tcp_client.set_disconnect_timeout(disconnect_timeout);
+        }
+
+        if let Some(reconnect_timeout) = builder.reconnect_timeout {
+            tcp_client = tcp_client.set_reconnect_timeout(reconnect_timeout);
+        }
 
         Self {
-            tcp_client: TcpClient::new("MyNoSqlClient".to_string(), Arc::new(settings)),
+            tcp_client,
+            settings,
             ping_timeout: Duration::from_secs(3),
-            connect_timeout: Duration::from_secs(3),
-            tcp_events: Arc::new(TcpEvents::new(
-                app_name.to_string(),
-                Arc::new(SyncToMainNodeHandler::new()),
-            )),
+            connect_timeout,
+            tcp_events,
             app_states: Arc::new(AppStates::create_un_initialized()),
         }
     }
@@ -54,19 +194,67 @@ impl MyNoSqlTcpConnection {
         TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + DeserializeOwned + 'static,
     >(
         &self,
-    ) -> Arc<MyNoSqlDataReaderTcp<TMyNoSqlEntity>> {
+    ) -> Result<Arc<MyNoSqlDataReaderTcp<TMyNoSqlEntity>>, GetReaderError> {
         self.tcp_events
-            .subscribers
-            .create_subscriber(
-                self.app_states.clone(),
-                self.tcp_events.sync_handler.clone(),
-            )
+            .subscribe_reader(self.app_states.clone())
             .await
     }
 
+    // Every get_reader call has to be released with remove_reader.
+    // Table is unsubscribed and cleared only when the last holder removes the reader
+    pub async fn remove_reader<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>(
+        &self,
+    ) -> RemoveReaderResult {
+        self.tcp_events
+            .unsubscribe_reader(TMyNoSqlEntity::TABLE_NAME)
+            .await
+    }
+
+    pub async fn get_active_endpoint(&self) -> Option<String> {
+        if !self.tcp_events.is_connected().await {
+            return None;
+        }
+
+        self.settings.get_last_host_port()
+    }
+
+    pub fn subscribe_to_errors(&self) -> watch::Receiver<Option<MyNoSqlReaderError>> {
+        self.tcp_events.subscribe_to_errors()
+    }
+
+    pub async fn wait_all_readers_initialized(&self, timeout: Duration) -> Result<(), WaitError> {
+        let readers = self.tcp_events.subscribers.get_all().await;
+
+        let wait_all = async {
+            for (_, reader) in readers.iter() {
+                reader.wait_until_first_data_arrives().await?;
+            }
+
+            Ok::<(), WaitError>(())
+        };
+
+        match tokio::time::timeout(timeout, wait_all).await {
+            Ok(result) => result,
+            Err(_) => {
+                let mut table_names = Vec::new();
+
+                for (table_name, reader) in readers.iter() {
+                    if !reader.has_entities_at_all().await {
+                        table_names.push(table_name.to_string());
+                    }
+                }
+
+                Err(WaitError::Timeout { table_names })
+            }
+        }
+    }
+
+    #[allow(deprecated)]
     pub async fn start(&self, logger: Arc<impl Logger + Send + Sync + 'static>) {
         self.app_states.set_initialized();
 
+        *self.settings.connect_timeout.lock().unwrap() = self.connect_timeout;
+
         self.tcp_client
             .start(
                 Arc::new(|| -> MyNoSqlReaderTcpSerializer { MyNoSqlReaderTcpSerializer::new() }),
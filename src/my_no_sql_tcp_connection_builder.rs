@@ -0,0 +1,73 @@
+use std::{sync::Arc, time::Duration};
+
+use rust_extensions::StrOrString;
+
+use crate::{
+    MyNoSqlTcpConnection, MyNoSqlTcpConnectionSettings, ReconnectBackoff, ServerErrorPolicy,
+};
+
+pub struct MyNoSqlTcpConnectionBuilder {
+    pub(crate) app_name: StrOrString<'static>,
+    pub(crate) settings: Arc<dyn MyNoSqlTcpConnectionSettings + Sync + Send + 'static>,
+    pub(crate) server_error_policy: ServerErrorPolicy,
+    pub(crate) ping_interval: Option<Duration>,
+    pub(crate) disconnect_timeout: Option<Duration>,
+    pub(crate) reconnect_timeout: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) reconnect_backoff: Option<ReconnectBackoff>,
+}
+
+impl MyNoSqlTcpConnectionBuilder {
+    pub fn new(
+        app_name: impl Into<StrOrString<'static>>,
+        settings: Arc<dyn MyNoSqlTcpConnectionSettings + Sync + Send + 'static>,
+    ) -> Self {
+        Self {
+            app_name: app_name.into(),
+            settings,
+            server_error_policy: ServerErrorPolicy::default(),
+            ping_interval: None,
+            disconnect_timeout: None,
+            reconnect_timeout: None,
+            connect_timeout: None,
+            reconnect_backoff: None,
+        }
+    }
+
+    pub fn set_server_error_policy(mut self, value: ServerErrorPolicy) -> Self {
+        self.server_error_policy = value;
+        self
+    }
+
+    // Applied with a second resolution, rounded up
+    pub fn set_ping_interval(mut self, value: Duration) -> Self {
+        self.ping_interval = Some(value);
+        self
+    }
+
+    pub fn set_disconnect_timeout(mut self, value: Duration) -> Self {
+        self.disconnect_timeout = Some(value);
+        self
+    }
+
+    pub fn set_reconnect_timeout(mut self, value: Duration) -> Self {
+        self.reconnect_timeout = Some(value);
+        self
+    }
+
+    // Each endpoint is probed with a plain TCP connect bounded by the timeout before
+    // the connection is made. 3 seconds by default
+    pub fn set_connect_timeout(mut self, value: Duration) -> Self {
+        self.connect_timeout = Some(value);
+        self
+    }
+
+    pub fn set_reconnect_backoff(mut self, value: ReconnectBackoff) -> Self {
+        self.reconnect_backoff = Some(value);
+        self
+    }
+
+    pub fn build(self) -> MyNoSqlTcpConnection {
+        MyNoSqlTcpConnection::from_builder(self)
+    }
+}
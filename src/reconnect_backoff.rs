@@ -0,0 +1,123 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: f64,
+    jitter: bool,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            max_delay,
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+
+    // Multiplier which is not finite or is below 1.0 is replaced with 1.0: the delay stays initial_delay
+    pub fn set_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = if multiplier.is_finite() && multiplier >= 1.0 {
+            multiplier
+        } else {
+            1.0
+        };
+        self
+    }
+
+    pub fn get_initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    pub fn get_max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    pub fn get_multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    pub fn get_jitter(&self) -> bool {
+        self.jitter
+    }
+
+    pub fn set_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // reconnect_no starts from 0 for the first reconnect attempt, which waits initial_delay
+    pub fn get_delay(&self, reconnect_no: usize) -> Duration {
+        let power = reconnect_no.min(32) as i32;
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(power);
+        if !delay.is_finite() || delay < 0.0 {
+            return self.max_delay;
+        }
+
+        let delay = delay.min(self.max_delay.as_secs_f64());
+
+        let delay = if self.jitter {
+            delay / 2.0 + delay / 2.0 * get_random_ratio()
+        } else {
+            delay
+        };
+
+        Duration::from_secs_f64(delay)
+    }
+}
+
+fn get_random_ratio() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    (hasher.finish() % 1000) as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ReconnectBackoff;
+
+    #[test]
+    fn test_delay_grows_until_max() {
+        let backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(10))
+            .set_jitter(false);
+
+        assert_eq!(Duration::from_secs(1), backoff.get_delay(0));
+        assert_eq!(Duration::from_secs(2), backoff.get_delay(1));
+        assert_eq!(Duration::from_secs(4), backoff.get_delay(2));
+        assert_eq!(Duration::from_secs(10), backoff.get_delay(4));
+        assert_eq!(Duration::from_secs(10), backoff.get_delay(100));
+    }
+
+    #[test]
+    fn test_invalid_multiplier_does_not_panic() {
+        for multiplier in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -2.0, 0.5] {
+            let backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(10))
+                .set_multiplier(multiplier)
+                .set_jitter(false);
+
+            assert_eq!(1.0, backoff.get_multiplier());
+            assert_eq!(Duration::from_secs(1), backoff.get_delay(0));
+            assert_eq!(Duration::from_secs(1), backoff.get_delay(100));
+        }
+    }
+
+    #[test]
+    fn test_jitter_keeps_delay_in_range() {
+        let backoff = ReconnectBackoff::new(Duration::from_secs(4), Duration::from_secs(10));
+
+        for _ in 0..100 {
+            let delay = backoff.get_delay(0);
+            assert!(delay >= Duration::from_secs(2));
+            assert!(delay <= Duration::from_secs(4));
+        }
+    }
+}
@@ -1,4 +1,9 @@
 #[async_trait::async_trait]
 pub trait MyNoSqlTcpConnectionSettings {
     async fn get_host_port(&self) -> String;
+
+    // Ordered list of endpoints. Connection moves to the next one on failure and back to the first one once stable
+    async fn get_host_ports(&self) -> Vec<String> {
+        vec![self.get_host_port().await]
+    }
 }
@@ -0,0 +1,226 @@
+use std::sync::{Arc, Mutex};
+
+use my_no_sql_core::db_json_entity::DbJsonEntity;
+use rust_extensions::Logger;
+
+#[derive(Debug, Clone)]
+pub struct BrokenEntity {
+    pub partition_key: Option<String>,
+    pub row_key: Option<String>,
+    pub raw: Vec<u8>,
+    pub error: String,
+}
+
+impl BrokenEntity {
+    pub fn new(data: &[u8], error: String) -> Self {
+        let (partition_key, row_key) = match DbJsonEntity::parse(data) {
+            Ok(db_entity) => (
+                Some(db_entity.partition_key.to_string()),
+                Some(db_entity.row_key.to_string()),
+            ),
+            Err(_) => (None, None),
+        };
+
+        Self {
+            partition_key,
+            row_key,
+            raw: data.to_vec(),
+            error,
+        }
+    }
+
+    fn has_same_keys(&self, other: &BrokenEntity) -> bool {
+        if self.partition_key.is_none() || self.row_key.is_none() {
+            return false;
+        }
+
+        self.partition_key == other.partition_key && self.row_key == other.row_key
+    }
+
+    fn has_keys(&self, partition_key: &str, row_key: &str) -> bool {
+        self.partition_key.as_deref() == Some(partition_key)
+            && self.row_key.as_deref() == Some(row_key)
+    }
+}
+
+pub trait BrokenEntitiesHandler {
+    fn broken_entities_found(&self, table_name: &'static str, found: usize, quarantined: usize);
+}
+
+pub struct LogBrokenEntitiesHandler;
+
+impl BrokenEntitiesHandler for LogBrokenEntitiesHandler {
+    fn broken_entities_found(&self, table_name: &'static str, found: usize, quarantined: usize) {
+        my_logger::LOGGER.write_error(
+            "MyNoSqlDataReader".to_string(),
+            format!(
+                "Table: {}. Skipped {} entities which can not be deserialized. Quarantined entities: {}",
+                table_name, found, quarantined
+            ),
+            None,
+        );
+    }
+}
+
+// The oldest quarantined entities are dropped once the limit is reached
+const MAX_QUARANTINED_ENTITIES: usize = 1024;
+
+pub struct BrokenEntities {
+    items: Mutex<Vec<BrokenEntity>>,
+    handler: Mutex<Arc<dyn BrokenEntitiesHandler + Send + Sync + 'static>>,
+}
+
+impl BrokenEntities {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(Vec::new()),
+            handler: Mutex::new(Arc::new(LogBrokenEntitiesHandler)),
+        }
+    }
+
+    pub fn set_handler(&self, handler: Arc<dyn BrokenEntitiesHandler + Send + Sync + 'static>) {
+        *self.handler.lock().unwrap() = handler;
+    }
+
+    pub fn quarantine(&self, table_name: &'static str, broken_entities: Vec<BrokenEntity>) {
+        let found = broken_entities.len();
+
+        let quarantined = {
+            let mut items = self.items.lock().unwrap();
+
+            for broken_entity in broken_entities {
+                items.retain(|itm| !itm.has_same_keys(&broken_entity));
+                items.push(broken_entity);
+            }
+
+            if items.len() > MAX_QUARANTINED_ENTITIES {
+                let to_drop = items.len() - MAX_QUARANTINED_ENTITIES;
+                items.drain(..to_drop);
+            }
+
+            items.len()
+        };
+
+        let handler = self.handler.lock().unwrap().clone();
+        handler.broken_entities_found(table_name, found, quarantined);
+    }
+
+    // Rows which were updated or deleted later are not broken anymore
+    pub fn release<'s>(&self, keys: impl Iterator<Item = (&'s str, &'s str)>) {
+        let mut items = self.items.lock().unwrap();
+
+        if items.is_empty() {
+            return;
+        }
+
+        for (partition_key, row_key) in keys {
+            items.retain(|itm| !itm.has_keys(partition_key, row_key));
+        }
+    }
+
+    pub fn release_partition(&self, partition_key: &str) {
+        self.items
+            .lock()
+            .unwrap()
+            .retain(|itm| itm.partition_key.as_deref() != Some(partition_key));
+    }
+
+    pub fn clear(&self) {
+        self.items.lock().unwrap().clear();
+    }
+
+    pub fn get_all(&self) -> Vec<BrokenEntity> {
+        self.items.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::{BrokenEntities, BrokenEntitiesHandler, BrokenEntity};
+
+    struct TestHandler {
+        found: AtomicUsize,
+    }
+
+    impl BrokenEntitiesHandler for TestHandler {
+        fn broken_entities_found(&self, _table_name: &'static str, found: usize, _: usize) {
+            self.found.fetch_add(found, Ordering::SeqCst);
+        }
+    }
+
+    fn create_broken_entity(partition_key: Option<&str>, row_key: Option<&str>) -> BrokenEntity {
+        BrokenEntity {
+            partition_key: partition_key.map(|itm| itm.to_string()),
+            row_key: row_key.map(|itm| itm.to_string()),
+            raw: vec![],
+            error: "Test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_same_row_is_quarantined_once() {
+        let handler = Arc::new(TestHandler {
+            found: AtomicUsize::new(0),
+        });
+
+        let broken_entities = BrokenEntities::new();
+        broken_entities.set_handler(handler.clone());
+
+        broken_entities.quarantine(
+            "Test",
+            vec![
+                create_broken_entity(Some("PK1"), Some("RK1")),
+                create_broken_entity(None, None),
+            ],
+        );
+
+        broken_entities.quarantine(
+            "Test",
+            vec![
+                create_broken_entity(Some("PK1"), Some("RK1")),
+                create_broken_entity(None, None),
+            ],
+        );
+
+        assert_eq!(3, broken_entities.get_all().len());
+        assert_eq!(4, handler.found.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_released_and_capped() {
+        let broken_entities = BrokenEntities::new();
+        broken_entities.set_handler(Arc::new(TestHandler {
+            found: AtomicUsize::new(0),
+        }));
+
+        broken_entities.quarantine(
+            "Test",
+            vec![
+                create_broken_entity(Some("PK1"), Some("RK1")),
+                create_broken_entity(Some("PK1"), Some("RK2")),
+                create_broken_entity(Some("PK2"), Some("RK1")),
+            ],
+        );
+
+        broken_entities.release(vec![("PK1", "RK1")].into_iter());
+        assert_eq!(2, broken_entities.get_all().len());
+
+        broken_entities.release_partition("PK2");
+        assert_eq!(1, broken_entities.get_all().len());
+
+        let many = (0..super::MAX_QUARANTINED_ENTITIES + 10)
+            .map(|_| create_broken_entity(None, None))
+            .collect();
+
+        broken_entities.quarantine("Test", many);
+        assert_eq!(
+            super::MAX_QUARANTINED_ENTITIES,
+            broken_entities.get_all().len()
+        );
+    }
+}
@@ -3,19 +3,67 @@ use std::{collections::BTreeMap, sync::Arc};
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use rust_extensions::lazy::LazyVec;
 
-use super::MyNoSqlDataReaderCallBacks;
+use super::{
+    MyNoSqlDataReaderCallBacks, MyNoSqlPartitionSnapshot, MyNoSqlTableEntities,
+    MyNoSqlTableSnapshot, UnchangedRowsPolicy,
+};
+
+// Receives the differences found by the triggers: the callbacks pusher and the views
+#[async_trait::async_trait]
+pub trait CallbackTriggersTarget<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    async fn inserted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>);
+    // Pairs of (old, new) versions of the same row
+    async fn updated(
+        &self,
+        partition_key: &str,
+        entities: Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>,
+    );
+    async fn deleted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>);
+}
+
+#[async_trait::async_trait]
+impl<TMyNoSqlEntity, TMyNoSqlDataReaderCallBacks> CallbackTriggersTarget<TMyNoSqlEntity>
+    for TMyNoSqlDataReaderCallBacks
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync,
+{
+    async fn inserted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
+        self.inserted_or_replaced(partition_key, entities).await;
+    }
+
+    async fn updated(
+        &self,
+        partition_key: &str,
+        entities: Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>,
+    ) {
+        let entities = entities.into_iter().map(|(_, new)| new).collect();
+        self.inserted_or_replaced(partition_key, entities).await;
+    }
+
+    async fn deleted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
+        MyNoSqlDataReaderCallBacks::deleted(self, partition_key, entities).await;
+    }
+}
 
 pub async fn trigger_table_difference<
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>,
+    TMyNoSqlDataReaderCallBacks: CallbackTriggersTarget<TMyNoSqlEntity> + ?Sized,
 >(
     callbacks: &TMyNoSqlDataReaderCallBacks,
-    before: Option<BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>>,
-    now_entities: &BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>,
+    before: Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>>,
+    now_entities: &MyNoSqlTableEntities<TMyNoSqlEntity>,
+    unchanged_rows_policy: UnchangedRowsPolicy,
 ) {
     match before {
         Some(before) => {
-            trigger_old_and_new_table_difference(callbacks, before, now_entities).await;
+            trigger_old_and_new_table_difference(
+                callbacks,
+                before,
+                now_entities,
+                unchanged_rows_policy,
+            )
+            .await;
         }
         None => {
             trigger_brand_new_table(callbacks, now_entities).await;
@@ -25,10 +73,10 @@ pub async fn trigger_table_difference<
 
 pub async fn trigger_brand_new_table<
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>,
+    TMyNoSqlDataReaderCallBacks: CallbackTriggersTarget<TMyNoSqlEntity> + ?Sized,
 >(
     callbacks: &TMyNoSqlDataReaderCallBacks,
-    now_entities: &BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>,
+    now_entities: &MyNoSqlTableEntities<TMyNoSqlEntity>,
 ) {
     for (partition_key, now_partition) in now_entities {
         let mut added = LazyVec::new();
@@ -37,38 +85,42 @@ pub async fn trigger_brand_new_table<
         }
 
         if let Some(added_entities) = added.get_result() {
-            callbacks
-                .inserted_or_replaced(partition_key, added_entities)
-                .await;
+            callbacks.inserted(partition_key, added_entities).await;
         }
     }
 }
 
 pub async fn trigger_old_and_new_table_difference<
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>,
+    TMyNoSqlDataReaderCallBacks: CallbackTriggersTarget<TMyNoSqlEntity> + ?Sized,
 >(
     callbacks: &TMyNoSqlDataReaderCallBacks,
-    mut before: BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>,
-    now_entities: &BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>,
+    before: MyNoSqlTableSnapshot<TMyNoSqlEntity>,
+    now_entities: &MyNoSqlTableEntities<TMyNoSqlEntity>,
+    unchanged_rows_policy: UnchangedRowsPolicy,
 ) {
     for (now_partition_key, now_partition) in now_entities {
-        let before_partition = before.remove(now_partition_key);
+        let before_partition = before.get(now_partition_key).cloned();
 
         trigger_partition_difference(
             callbacks,
             now_partition_key,
             before_partition,
-            now_partition,
+            now_partition.as_ref(),
+            unchanged_rows_policy,
         )
         .await;
     }
 
-    for (before_partition_key, before_partition) in before {
+    for (before_partition_key, before_partition) in before.iter() {
+        if now_entities.contains_key(before_partition_key) {
+            continue;
+        }
+
         let mut deleted = LazyVec::new();
 
-        for (_, db_row) in before_partition {
-            deleted.add(db_row);
+        for db_row in before_partition.values() {
+            deleted.add(db_row.clone());
         }
 
         if let Some(deleted_entities) = deleted.get_result() {
@@ -81,38 +133,50 @@ pub async fn trigger_old_and_new_table_difference<
 
 pub async fn trigger_partition_difference<
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>,
+    TMyNoSqlDataReaderCallBacks: CallbackTriggersTarget<TMyNoSqlEntity> + ?Sized,
 >(
     callbacks: &TMyNoSqlDataReaderCallBacks,
     partition_key: &str,
-    before_partition: Option<BTreeMap<String, Arc<TMyNoSqlEntity>>>,
+    before_partition: Option<MyNoSqlPartitionSnapshot<TMyNoSqlEntity>>,
     now_partition: &BTreeMap<String, Arc<TMyNoSqlEntity>>,
+    unchanged_rows_policy: UnchangedRowsPolicy,
 ) {
     match before_partition {
-        Some(mut before_partition) => {
-            for (now_row_key, now_row) in now_partition {
-                let mut inserted_or_replaced = LazyVec::new();
+        Some(before_partition) => {
+            let mut inserted = LazyVec::new();
+            let mut updated = LazyVec::new();
 
-                match before_partition.remove(now_row_key) {
-                    Some(_) => {
-                        inserted_or_replaced.add(now_row.clone());
+            for (now_row_key, now_row) in now_partition {
+                match before_partition.get(now_row_key) {
+                    Some(before_row) => {
+                        if unchanged_rows_policy == UnchangedRowsPolicy::Skip
+                            && is_unchanged(before_row, now_row)
+                        {
+                            continue;
+                        }
+
+                        updated.add((before_row.clone(), now_row.clone()));
                     }
                     None => {
-                        inserted_or_replaced.add(now_row.clone());
+                        inserted.add(now_row.clone());
                     }
                 }
+            }
 
-                if let Some(inserted_or_replaced) = inserted_or_replaced.get_result() {
-                    callbacks
-                        .inserted_or_replaced(partition_key, inserted_or_replaced)
-                        .await;
-                }
+            if let Some(inserted) = inserted.get_result() {
+                callbacks.inserted(partition_key, inserted).await;
+            }
+
+            if let Some(updated) = updated.get_result() {
+                callbacks.updated(partition_key, updated).await;
             }
 
             let mut deleted_entities = LazyVec::new();
 
-            for (_, before_row) in before_partition {
-                deleted_entities.add(before_row);
+            for (before_row_key, before_row) in before_partition.iter() {
+                if !now_partition.contains_key(before_row_key) {
+                    deleted_entities.add(before_row.clone());
+                }
             }
 
             if let Some(deleted_entities) = deleted_entities.get_result() {
@@ -125,23 +189,34 @@ pub async fn trigger_partition_difference<
     }
 }
 
+// Zero timestamp means the entity does not track it, so we can not prove the row is the same
+fn is_unchanged<TMyNoSqlEntity: MyNoSqlEntity>(
+    before: &Arc<TMyNoSqlEntity>,
+    now: &Arc<TMyNoSqlEntity>,
+) -> bool {
+    if Arc::ptr_eq(before, now) {
+        return true;
+    }
+
+    let time_stamp = now.get_time_stamp();
+    time_stamp != 0 && before.get_time_stamp() == time_stamp
+}
+
 pub async fn trigger_brand_new_partition<
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>,
+    TMyNoSqlDataReaderCallBacks: CallbackTriggersTarget<TMyNoSqlEntity> + ?Sized,
 >(
     callbacks: &TMyNoSqlDataReaderCallBacks,
     partition_key: &str,
     partition: &BTreeMap<String, Arc<TMyNoSqlEntity>>,
 ) {
-    let mut inserted_or_replaced = LazyVec::new();
+    let mut inserted = LazyVec::new();
     for entity in partition.values() {
-        inserted_or_replaced.add(entity.clone());
+        inserted.add(entity.clone());
     }
 
-    if let Some(inserted_or_replaced_entities) = inserted_or_replaced.get_result() {
-        callbacks
-            .inserted_or_replaced(partition_key, inserted_or_replaced_entities)
-            .await;
+    if let Some(inserted_entities) = inserted.get_result() {
+        callbacks.inserted(partition_key, inserted_entities).await;
     }
 }
 
@@ -152,10 +227,12 @@ mod tests {
         sync::Arc,
     };
 
-    use my_no_sql_server_abstractions::MyNoSqlEntity;
     use tokio::sync::Mutex;
 
-    use crate::subscribers::MyNoSqlDataReaderCallBacks;
+    use crate::subscribers::{
+        test_row::TestRow, MyNoSqlDataReaderCallBacks, MyNoSqlTableEntities, UnchangedRowsPolicy,
+    };
+    use crate::subscribers::{test_row::TestRow, MyNoSqlDataReaderCallBacks, UnchangedRowsPolicy};
 
     struct TestCallbacksInner {
         inserted_or_replaced_entities: HashMap<String, Vec<Arc<TestRow>>>,
@@ -212,36 +289,6 @@ mod tests {
             }
         }
     }
-    pub struct TestRow {
-        partition_key: String,
-        row_key: String,
-        timestamp: i64,
-    }
-
-    impl TestRow {
-        pub fn new(partition_key: String, row_key: String, timestamp: i64) -> Self {
-            TestRow {
-                partition_key,
-                row_key,
-                timestamp,
-            }
-        }
-    }
-
-    impl MyNoSqlEntity for TestRow {
-        const TABLE_NAME: &'static str = "Test";
-
-        fn get_partition_key(&self) -> &str {
-            self.partition_key.as_str()
-        }
-        fn get_row_key(&self) -> &str {
-            self.row_key.as_str()
-        }
-        fn get_time_stamp(&self) -> i64 {
-            self.timestamp
-        }
-    }
-
     #[tokio::test]
     pub async fn test_we_had_data_in_table_and_new_table_is_empty() {
         let test_callback = TestCallbacks::new();
@@ -259,11 +306,17 @@ mod tests {
 
         let mut before = BTreeMap::new();
 
-        before.insert("PK1".to_string(), before_rows);
+        before.insert("PK1".to_string(), Arc::new(before_rows));
 
         let after = BTreeMap::new();
 
-        super::trigger_table_difference(&test_callback, Some(before), &after).await;
+        super::trigger_table_difference(
+            &test_callback,
+            Some(Arc::new(before)),
+            &after,
+            UnchangedRowsPolicy::Skip,
+        )
+        .await;
 
         let read_access = test_callback.data.lock().await;
 
@@ -287,9 +340,10 @@ mod tests {
 
         let mut after = BTreeMap::new();
 
-        after.insert("PK1".to_string(), after_rows);
+        after.insert("PK1".to_string(), Arc::new(after_rows));
 
-        super::trigger_table_difference(&test_callback, None, &after).await;
+        super::trigger_table_difference(&test_callback, None, &after, UnchangedRowsPolicy::Skip)
+            .await;
 
         let read_access = test_callback.data.lock().await;
         assert_eq!(
@@ -318,7 +372,7 @@ mod tests {
         );
 
         let mut before = BTreeMap::new();
-        before.insert("PK1".to_string(), before_partition);
+        before.insert("PK1".to_string(), Arc::new(before_partition));
 
         let mut after_partition = BTreeMap::new();
         after_partition.insert(
@@ -327,9 +381,15 @@ mod tests {
         );
 
         let mut after = BTreeMap::new();
-        after.insert("PK1".to_string(), after_partition);
+        after.insert("PK1".to_string(), Arc::new(after_partition));
 
-        super::trigger_table_difference(&test_callback, Some(before), &after).await;
+        super::trigger_table_difference(
+            &test_callback,
+            Some(Arc::new(before)),
+            &after,
+            UnchangedRowsPolicy::Skip,
+        )
+        .await;
 
         let read_access = test_callback.data.lock().await;
         assert_eq!(
@@ -342,4 +402,106 @@ mod tests {
         );
         assert_eq!(1, read_access.deleted.get("PK1").unwrap().len());
     }
+
+    struct TestExtendedCallbacks {
+        updated: Mutex<Vec<(Arc<TestRow>, Arc<TestRow>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CallbackTriggersTarget<TestRow> for TestExtendedCallbacks {
+        async fn inserted(&self, _partition_key: &str, _entities: Vec<Arc<TestRow>>) {}
+
+        async fn updated(&self, _partition_key: &str, entities: Vec<(Arc<TestRow>, Arc<TestRow>)>) {
+            self.updated.lock().await.extend(entities);
+        }
+
+        async fn deleted(&self, _partition_key: &str, _entities: Vec<Arc<TestRow>>) {}
+    }
+
+    #[tokio::test]
+    pub async fn test_updated_rows_carry_old_value() {
+        let test_callback = TestExtendedCallbacks {
+            updated: Mutex::new(Vec::new()),
+        };
+
+        let mut before_partition = BTreeMap::new();
+        before_partition.insert(
+            "RK1".to_string(),
+            Arc::new(TestRow::new("PK1".to_string(), "RK1".to_string(), 1)),
+        );
+
+        let mut after_partition = BTreeMap::new();
+        after_partition.insert(
+            "RK1".to_string(),
+            Arc::new(TestRow::new("PK1".to_string(), "RK1".to_string(), 2)),
+        );
+
+        super::trigger_partition_difference(
+            &test_callback,
+            "PK1",
+            Some(Arc::new(before_partition)),
+            &after_partition,
+            UnchangedRowsPolicy::Skip,
+        )
+        .await;
+
+        let updated = test_callback.updated.lock().await;
+        assert_eq!(1, updated.len());
+        assert_eq!(1, updated[0].0.get_time_stamp());
+        assert_eq!(2, updated[0].1.get_time_stamp());
+    }
+
+    #[tokio::test]
+    pub async fn test_unchanged_rows_are_skipped_on_reinit() {
+        let mut before_partition = BTreeMap::new();
+        before_partition.insert(
+            "RK1".to_string(),
+            Arc::new(TestRow::new("PK1".to_string(), "RK1".to_string(), 1)),
+        );
+
+        let mut after_partition = BTreeMap::new();
+        after_partition.insert(
+            "RK1".to_string(),
+            Arc::new(TestRow::new("PK1".to_string(), "RK1".to_string(), 1)),
+        );
+
+        let before_partition = Arc::new(before_partition);
+
+        let test_callback = TestCallbacks::new();
+
+        super::trigger_partition_difference(
+            &test_callback,
+            "PK1",
+            Some(before_partition.clone()),
+            &after_partition,
+            UnchangedRowsPolicy::Skip,
+        )
+        .await;
+
+        let read_access = test_callback.data.lock().await;
+        assert!(read_access
+            .inserted_or_replaced_entities
+            .get("PK1")
+            .is_none());
+        drop(read_access);
+
+        super::trigger_partition_difference(
+            &test_callback,
+            "PK1",
+            Some(before_partition),
+            &after_partition,
+            UnchangedRowsPolicy::Emit,
+        )
+        .await;
+
+        let read_access = test_callback.data.lock().await;
+        assert_eq!(
+            1,
+            read_access
+                .inserted_or_replaced_entities
+                .get("PK1")
+                .unwrap()
+                .len()
+        );
+    }
 }
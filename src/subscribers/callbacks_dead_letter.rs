@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use rust_extensions::Logger;
+
+pub enum CallbackEvent<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    Inserted(String, Vec<Arc<TMyNoSqlEntity>>),
+    Updated(String, Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>),
+    Deleted(String, Vec<Arc<TMyNoSqlEntity>>),
+    ResyncPartition(String, Vec<Arc<TMyNoSqlEntity>>),
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> Clone
+    for CallbackEvent<TMyNoSqlEntity>
+{
+    fn clone(&self) -> Self {
+        match self {
+            CallbackEvent::Inserted(partition_key, entities) => {
+                CallbackEvent::Inserted(partition_key.clone(), entities.clone())
+            }
+            CallbackEvent::Updated(partition_key, entities) => {
+                CallbackEvent::Updated(partition_key.clone(), entities.clone())
+            }
+            CallbackEvent::Deleted(partition_key, entities) => {
+                CallbackEvent::Deleted(partition_key.clone(), entities.clone())
+            }
+            CallbackEvent::ResyncPartition(partition_key, entities) => {
+                CallbackEvent::ResyncPartition(partition_key.clone(), entities.clone())
+            }
+        }
+    }
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> CallbackEvent<TMyNoSqlEntity> {
+    pub fn get_partition_key(&self) -> &str {
+        match self {
+            CallbackEvent::Inserted(partition_key, _) => partition_key,
+            CallbackEvent::Updated(partition_key, _) => partition_key,
+            CallbackEvent::Deleted(partition_key, _) => partition_key,
+            CallbackEvent::ResyncPartition(partition_key, _) => partition_key,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CallbackError {
+    Failed(String),
+    Panicked(String),
+}
+
+pub struct CallbacksDeadLetter<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    pub table_name: &'static str,
+    // Version of the read view the event was delivered with
+    pub version: u64,
+    pub event: CallbackEvent<TMyNoSqlEntity>,
+    // Error of the last attempt
+    pub error: CallbackError,
+    pub attempts: usize,
+}
+
+#[async_trait::async_trait]
+pub trait CallbacksDeadLetterHandler<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    async fn dead_letter(&self, dead_letter: CallbacksDeadLetter<TMyNoSqlEntity>);
+}
+
+pub struct LogCallbacksDeadLetterHandler;
+
+#[async_trait::async_trait]
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static>
+    CallbacksDeadLetterHandler<TMyNoSqlEntity> for LogCallbacksDeadLetterHandler
+{
+    async fn dead_letter(&self, dead_letter: CallbacksDeadLetter<TMyNoSqlEntity>) {
+        my_logger::LOGGER.write_error(
+            "MyNoSqlDataReaderCallBacks".to_string(),
+            format!(
+                "Table: {}. Partition: {}. Callback failed after {} attempts: {:?}",
+                dead_letter.table_name,
+                dead_letter.event.get_partition_key(),
+                dead_letter.attempts,
+                dead_letter.error
+            ),
+            None,
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use crate::ReconnectBackoff;
+
+#[derive(Debug, Clone)]
+pub struct CallbacksRetryPolicy {
+    // Including the first call. 1 means the failed event goes straight to the dead letter handler
+    pub max_attempts: usize,
+    pub backoff: ReconnectBackoff,
+}
+
+impl CallbacksRetryPolicy {
+    pub fn new(max_attempts: usize, backoff: ReconnectBackoff) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff,
+        }
+    }
+
+    pub fn no_retries() -> Self {
+        Self::new(
+            1,
+            ReconnectBackoff::new(Duration::from_secs(0), Duration::from_secs(0)),
+        )
+    }
+
+    // None means the event must not be retried anymore
+    pub fn get_delay(&self, failed_attempts: usize) -> Option<Duration> {
+        if failed_attempts >= self.max_attempts {
+            return None;
+        }
+
+        Some(self.backoff.get_delay(failed_attempts))
+    }
+}
+
+impl Default for CallbacksRetryPolicy {
+    fn default() -> Self {
+        Self::no_retries()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::CallbacksRetryPolicy;
+    use crate::ReconnectBackoff;
+
+    #[test]
+    fn test_retries_stop_after_max_attempts() {
+        let policy = CallbacksRetryPolicy::new(
+            3,
+            ReconnectBackoff::new(Duration::from_millis(100), Duration::from_secs(1))
+                .set_jitter(false),
+        );
+
+        assert_eq!(Some(Duration::from_millis(100)), policy.get_delay(1));
+        assert_eq!(Some(Duration::from_millis(200)), policy.get_delay(2));
+        assert_eq!(None, policy.get_delay(3));
+
+        assert_eq!(None, CallbacksRetryPolicy::no_retries().get_delay(1));
+    }
+}
@@ -65,6 +65,60 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> GetEntitiesBuilder<T
         self
     }
 
+    pub fn set_row_key_from(mut self, row_key: impl Into<String>, inclusive: bool) -> Self {
+        match &mut self {
+            GetEntitiesBuilder::Inner(inner) => inner.set_row_key_from(row_key.into(), inclusive),
+            #[cfg(feature = "mocks")]
+            GetEntitiesBuilder::Mock(inner) => inner.set_row_key_from(row_key.into(), inclusive),
+        }
+        self
+    }
+
+    pub fn set_row_key_to(mut self, row_key: impl Into<String>, inclusive: bool) -> Self {
+        match &mut self {
+            GetEntitiesBuilder::Inner(inner) => inner.set_row_key_to(row_key.into(), inclusive),
+            #[cfg(feature = "mocks")]
+            GetEntitiesBuilder::Mock(inner) => inner.set_row_key_to(row_key.into(), inclusive),
+        }
+        self
+    }
+
+    pub fn set_row_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        match &mut self {
+            GetEntitiesBuilder::Inner(inner) => inner.set_row_key_prefix(prefix.into()),
+            #[cfg(feature = "mocks")]
+            GetEntitiesBuilder::Mock(inner) => inner.set_row_key_prefix(prefix.into()),
+        }
+        self
+    }
+
+    pub fn set_skip(mut self, value: usize) -> Self {
+        match &mut self {
+            GetEntitiesBuilder::Inner(inner) => inner.set_skip(value),
+            #[cfg(feature = "mocks")]
+            GetEntitiesBuilder::Mock(inner) => inner.set_skip(value),
+        }
+        self
+    }
+
+    pub fn set_limit(mut self, value: usize) -> Self {
+        match &mut self {
+            GetEntitiesBuilder::Inner(inner) => inner.set_limit(value),
+            #[cfg(feature = "mocks")]
+            GetEntitiesBuilder::Mock(inner) => inner.set_limit(value),
+        }
+        self
+    }
+
+    pub fn set_reverse(mut self) -> Self {
+        match &mut self {
+            GetEntitiesBuilder::Inner(inner) => inner.set_reverse(),
+            #[cfg(feature = "mocks")]
+            GetEntitiesBuilder::Mock(inner) => inner.set_reverse(),
+        }
+        self
+    }
+
     pub async fn get_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
         match &self {
             GetEntitiesBuilder::Inner(inner) => inner.get_as_vec().await,
@@ -1,14 +1,15 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 
-use super::super::my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner;
+use super::{super::my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner, RowKeysRange};
 
 pub struct GetEntitiesBuilderInner<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
     partition_key: String,
     update_statistic_data: UpdateEntityStatisticsData,
+    range: RowKeysRange,
     inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>,
 }
 
@@ -19,6 +20,7 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
         Self {
             partition_key,
             update_statistic_data: UpdateEntityStatisticsData::default(),
+            range: RowKeysRange::new(),
             inner,
         }
     }
@@ -39,34 +41,58 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
         self.update_statistic_data.row_expiration_moment = Some(value);
     }
 
-    pub async fn get_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let db_rows = {
-            let reader = self.inner.get_data().read().await;
-            reader.get_by_partition_as_vec(self.partition_key.as_str())
-        }?;
+    pub fn set_row_key_from(&mut self, row_key: String, inclusive: bool) {
+        self.range.from = if inclusive {
+            Bound::Included(row_key)
+        } else {
+            Bound::Excluded(row_key)
+        };
+    }
 
-        self.inner
-            .get_sync_handler()
-            .event_notifier
-            .update(
-                TMyNoSqlEntity::TABLE_NAME,
-                &self.partition_key,
-                || db_rows.iter().map(|itm| itm.get_row_key()),
-                &self.update_statistic_data,
-            )
-            .await;
+    pub fn set_row_key_to(&mut self, row_key: String, inclusive: bool) {
+        self.range.to = if inclusive {
+            Bound::Included(row_key)
+        } else {
+            Bound::Excluded(row_key)
+        };
+    }
 
-        Some(db_rows)
+    pub fn set_row_key_prefix(&mut self, prefix: String) {
+        self.range.prefix = Some(prefix);
     }
 
-    pub async fn get_as_vec_with_filter(
+    pub fn set_skip(&mut self, value: usize) {
+        self.range.skip = value;
+    }
+
+    pub fn set_limit(&mut self, value: usize) {
+        self.range.limit = Some(value);
+    }
+
+    pub fn set_reverse(&mut self) {
+        self.range.reverse = true;
+    }
+
+    async fn select(
         &self,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
+        empty_partition_as_none: bool,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let db_rows = {
-            let reader = self.inner.get_data().read().await;
-            reader.get_by_partition_as_vec_with_filter(&self.partition_key, filter)
-        }?;
+        let partition = self
+            .inner
+            .get_entities()
+            .get_partition_snapshot(self.partition_key.as_str())?;
+
+        if empty_partition_as_none && partition.is_empty() {
+            return None;
+        }
+
+        let db_rows: Vec<Arc<TMyNoSqlEntity>> = self
+            .range
+            .select(partition.as_ref(), filter)
+            .into_iter()
+            .cloned()
+            .collect();
 
         self.inner
             .get_sync_handler()
@@ -82,46 +108,40 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
         Some(db_rows)
     }
 
-    pub async fn get_as_btree_map(&self) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let db_rows = {
-            let reader = self.inner.get_data().read().await;
-            reader.get_by_partition(&self.partition_key)
-        }?;
+    // Empty partition gives None, while the btree map getters return an empty map for it
+    pub async fn get_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.select(|_| true, true).await
+    }
 
-        self.inner
-            .get_sync_handler()
-            .event_notifier
-            .update(
-                TMyNoSqlEntity::TABLE_NAME,
-                &self.partition_key,
-                || db_rows.values().map(|itm| itm.get_row_key()),
-                &self.update_statistic_data,
-            )
-            .await;
+    pub async fn get_as_vec_with_filter(
+        &self,
+        filter: impl Fn(&TMyNoSqlEntity) -> bool,
+    ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.select(filter, true).await
+    }
 
-        Some(db_rows)
+    pub async fn get_as_btree_map(&self) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
+        let db_rows = self.select(|_| true, false).await?;
+        Some(to_btree_map(db_rows))
     }
 
     pub async fn get_as_btree_map_with_filter(
         &self,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let db_rows = {
-            let reader = self.inner.get_data().read().await;
-            reader.get_by_partition_with_filter(&self.partition_key, filter)
-        }?;
+        let db_rows = self.select(filter, false).await?;
+        Some(to_btree_map(db_rows))
+    }
+}
 
-        self.inner
-            .get_sync_handler()
-            .event_notifier
-            .update(
-                TMyNoSqlEntity::TABLE_NAME,
-                &self.partition_key,
-                || db_rows.values().map(|itm| itm.get_row_key()),
-                &self.update_statistic_data,
-            )
-            .await;
+pub fn to_btree_map<TMyNoSqlEntity: MyNoSqlEntity>(
+    db_rows: Vec<Arc<TMyNoSqlEntity>>,
+) -> BTreeMap<String, Arc<TMyNoSqlEntity>> {
+    let mut result = BTreeMap::new();
 
-        Some(db_rows)
+    for db_row in db_rows {
+        result.insert(db_row.get_row_key().to_string(), db_row);
     }
+
+    result
 }
@@ -1,14 +1,16 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
 use rust_extensions::date_time::DateTimeAsMicroseconds;
 
+use super::{to_btree_map, RowKeysRange};
 use crate::subscribers::MyNoSqlDataReaderMockInner;
 
 pub struct GetEntitiesBuilderMock<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
     partition_key: String,
     update_statistic_data: UpdateEntityStatisticsData,
+    range: RowKeysRange,
     inner: Arc<MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
 }
 
@@ -20,6 +22,7 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> GetEntitiesBuilderMo
         Self {
             partition_key,
             update_statistic_data: UpdateEntityStatisticsData::default(),
+            range: RowKeysRange::new(),
             inner,
         }
     }
@@ -40,51 +43,73 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> GetEntitiesBuilderMo
         self.update_statistic_data.row_expiration_moment = Some(value);
     }
 
+    pub fn set_row_key_from(&mut self, row_key: String, inclusive: bool) {
+        self.range.from = if inclusive {
+            Bound::Included(row_key)
+        } else {
+            Bound::Excluded(row_key)
+        };
+    }
+
+    pub fn set_row_key_to(&mut self, row_key: String, inclusive: bool) {
+        self.range.to = if inclusive {
+            Bound::Included(row_key)
+        } else {
+            Bound::Excluded(row_key)
+        };
+    }
+
+    pub fn set_row_key_prefix(&mut self, prefix: String) {
+        self.range.prefix = Some(prefix);
+    }
+
+    pub fn set_skip(&mut self, value: usize) {
+        self.range.skip = value;
+    }
+
+    pub fn set_limit(&mut self, value: usize) {
+        self.range.limit = Some(value);
+    }
+
+    pub fn set_reverse(&mut self) {
+        self.range.reverse = true;
+    }
+
+    fn select(&self, filter: impl Fn(&TMyNoSqlEntity) -> bool) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let read_access = self.inner.data.read().unwrap();
+        let partition = read_access.get(self.partition_key.as_str())?;
+
+        let result = self
+            .range
+            .select(partition, filter)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        Some(result)
+    }
+
     pub async fn get_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        self.inner
-            .get_by_partition_key_as_vec(&self.partition_key)
-            .await
+        self.select(|_| true)
     }
 
     pub async fn get_as_vec_with_filter(
         &self,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        self.inner
-            .get_by_partition_key_as_vec_with_filter(&self.partition_key, filter)
-            .await
+        self.select(filter)
     }
 
     pub async fn get_as_btree_map(&self) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let items = self
-            .inner
-            .get_by_partition_key_as_vec(self.partition_key.as_str())
-            .await?;
-
-        let mut result = BTreeMap::new();
-
-        for item in items {
-            result.insert(item.get_row_key().to_string(), item);
-        }
-
-        Some(result)
+        let items = self.select(|_| true)?;
+        Some(to_btree_map(items))
     }
 
     pub async fn get_as_btree_map_with_filter(
         &self,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let items = self
-            .inner
-            .get_by_partition_key_as_vec_with_filter(self.partition_key.as_str(), filter)
-            .await?;
-
-        let mut result = BTreeMap::new();
-
-        for item in items {
-            result.insert(item.get_row_key().to_string(), item);
-        }
-
-        Some(result)
+        let items = self.select(filter)?;
+        Some(to_btree_map(items))
     }
 }
@@ -2,6 +2,8 @@ mod get_entities_builder;
 pub use get_entities_builder::*;
 mod get_entities_builder_inner;
 pub use get_entities_builder_inner::*;
+mod row_keys_range;
+pub use row_keys_range::*;
 #[cfg(feature = "mocks")]
 mod get_entities_builder_mock;
 #[cfg(feature = "mocks")]
@@ -0,0 +1,185 @@
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
+
+pub struct RowKeysRange {
+    pub from: Bound<String>,
+    pub to: Bound<String>,
+    pub prefix: Option<String>,
+    pub skip: usize,
+    pub limit: Option<usize>,
+    pub reverse: bool,
+}
+
+impl RowKeysRange {
+    pub fn new() -> Self {
+        Self {
+            from: Bound::Unbounded,
+            to: Bound::Unbounded,
+            prefix: None,
+            skip: 0,
+            limit: None,
+            reverse: false,
+        }
+    }
+
+    fn get_from(&self) -> Bound<&str> {
+        match &self.from {
+            Bound::Included(value) => Bound::Included(value.as_str()),
+            Bound::Excluded(value) => Bound::Excluded(value.as_str()),
+            Bound::Unbounded => match &self.prefix {
+                Some(prefix) => Bound::Included(prefix.as_str()),
+                None => Bound::Unbounded,
+            },
+        }
+    }
+
+    fn get_to<'s>(&'s self, prefix_end: Option<&'s str>) -> Bound<&'s str> {
+        match &self.to {
+            Bound::Included(value) => Bound::Included(value.as_str()),
+            Bound::Excluded(value) => Bound::Excluded(value.as_str()),
+            Bound::Unbounded => match prefix_end {
+                Some(prefix_end) => Bound::Excluded(prefix_end),
+                None => Bound::Unbounded,
+            },
+        }
+    }
+
+    // BTreeMap::range panics on such ranges, so we treat them as empty
+    fn is_empty_range(from: Bound<&str>, to: Bound<&str>) -> bool {
+        match (from, to) {
+            (Bound::Included(from), Bound::Included(to)) => from > to,
+            (Bound::Included(from), Bound::Excluded(to)) => from > to,
+            (Bound::Excluded(from), Bound::Included(to)) => from > to,
+            (Bound::Excluded(from), Bound::Excluded(to)) => from >= to,
+            _ => false,
+        }
+    }
+
+    pub fn select<'s, TMyNoSqlEntity>(
+        &'s self,
+        partition: &'s BTreeMap<String, Arc<TMyNoSqlEntity>>,
+        filter: impl Fn(&TMyNoSqlEntity) -> bool,
+    ) -> Vec<&'s Arc<TMyNoSqlEntity>> {
+        let prefix_end = self.prefix.as_deref().and_then(get_prefix_end);
+
+        let from = self.get_from();
+        let to = self.get_to(prefix_end.as_deref());
+
+        if Self::is_empty_range(from, to) {
+            return vec![];
+        }
+
+        let range = partition.range::<str, _>((from, to));
+
+        let range: Box<dyn Iterator<Item = (&'s String, &'s Arc<TMyNoSqlEntity>)> + 's> =
+            if self.reverse {
+                Box::new(range.rev())
+            } else {
+                Box::new(range)
+            };
+
+        let prefix = self.prefix.as_deref();
+
+        let items = range
+            .skip_while(|(row_key, _)| match prefix {
+                Some(prefix) => !row_key.starts_with(prefix),
+                None => false,
+            })
+            .take_while(|(row_key, _)| match prefix {
+                Some(prefix) => row_key.starts_with(prefix),
+                None => true,
+            })
+            .map(|(_, entity)| entity)
+            .filter(|entity| filter(entity))
+            .skip(self.skip);
+
+        match self.limit {
+            Some(limit) => items.take(limit).collect(),
+            None => items.collect(),
+        }
+    }
+}
+
+// Smallest key which is greater than every key starting with the prefix: the last char which
+// can be incremented is incremented and the chars after it are dropped
+fn get_prefix_end(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+
+    while let Some(last) = chars.pop() {
+        let mut next = last as u32 + 1;
+
+        // Surrogates are not valid chars
+        if next == 0xD800 {
+            next = 0xE000;
+        }
+
+        if let Some(next) = char::from_u32(next) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, ops::Bound, sync::Arc};
+
+    use super::{get_prefix_end, RowKeysRange};
+
+    fn create_partition() -> BTreeMap<String, Arc<String>> {
+        let mut result = BTreeMap::new();
+
+        for row_key in ["a1", "a2", "b1", "b2", "b3", "c1"] {
+            result.insert(row_key.to_string(), Arc::new(row_key.to_string()));
+        }
+
+        result
+    }
+
+    fn select(range: &RowKeysRange, partition: &BTreeMap<String, Arc<String>>) -> Vec<String> {
+        range
+            .select(partition, |_| true)
+            .into_iter()
+            .map(|itm| itm.as_ref().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_prefix_with_reverse_and_limit() {
+        let partition = create_partition();
+
+        let mut range = RowKeysRange::new();
+        range.prefix = Some("b".to_string());
+        assert_eq!(vec!["b1", "b2", "b3"], select(&range, &partition));
+
+        range.reverse = true;
+        range.limit = Some(2);
+        assert_eq!(vec!["b3", "b2"], select(&range, &partition));
+    }
+
+    #[test]
+    fn test_from_to_bounds() {
+        let partition = create_partition();
+
+        let mut range = RowKeysRange::new();
+        range.from = Bound::Excluded("a2".to_string());
+        range.to = Bound::Included("b2".to_string());
+        range.skip = 1;
+        assert_eq!(vec!["b2"], select(&range, &partition));
+
+        range.from = Bound::Excluded("b2".to_string());
+        range.to = Bound::Excluded("b2".to_string());
+        assert!(select(&range, &partition).is_empty());
+    }
+
+    #[test]
+    fn test_prefix_end() {
+        assert_eq!(Some("c".to_string()), get_prefix_end("b"));
+        assert_eq!(Some("ac".to_string()), get_prefix_end("ab"));
+        assert_eq!(Some("b".to_string()), get_prefix_end("a\u{10FFFF}"));
+        assert_eq!(Some("\u{E000}".to_string()), get_prefix_end("\u{D7FF}"));
+        assert_eq!(None, get_prefix_end("\u{10FFFF}"));
+        assert_eq!(None, get_prefix_end(""));
+    }
+}
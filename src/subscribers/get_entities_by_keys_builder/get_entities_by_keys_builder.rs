@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+#[cfg(feature = "mocks")]
+use super::GetEntitiesByKeysBuilderMock;
+use super::{
+    super::my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner, GetEntitiesByKeysBuilderInner,
+};
+
+pub enum GetEntitiesByKeysBuilder<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    Inner(GetEntitiesByKeysBuilderInner<'s, TMyNoSqlEntity>),
+    #[cfg(feature = "mocks")]
+    Mock(GetEntitiesByKeysBuilderMock<'s, TMyNoSqlEntity>),
+}
+
+impl<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
+    GetEntitiesByKeysBuilder<'s, TMyNoSqlEntity>
+{
+    pub fn new(
+        keys: &'s [(&'s str, &'s str)],
+        inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>,
+    ) -> Self {
+        Self::Inner(GetEntitiesByKeysBuilderInner::new(keys, inner))
+    }
+
+    #[cfg(feature = "mocks")]
+    pub fn new_mock(
+        keys: &'s [(&'s str, &'s str)],
+        inner: Arc<crate::subscribers::MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
+    ) -> Self {
+        Self::Mock(GetEntitiesByKeysBuilderMock::new(keys, inner))
+    }
+
+    pub fn set_partition_last_read_moment(mut self) -> Self {
+        match &mut self {
+            GetEntitiesByKeysBuilder::Inner(inner) => inner.set_partition_last_read_moment(),
+            #[cfg(feature = "mocks")]
+            GetEntitiesByKeysBuilder::Mock(inner) => inner.set_partition_last_read_moment(),
+        }
+
+        self
+    }
+
+    pub fn set_row_last_read_moment(mut self) -> Self {
+        match &mut self {
+            GetEntitiesByKeysBuilder::Inner(inner) => inner.set_row_last_read_moment(),
+            #[cfg(feature = "mocks")]
+            GetEntitiesByKeysBuilder::Mock(inner) => inner.set_row_last_read_moment(),
+        }
+
+        self
+    }
+
+    pub fn set_partition_expiration_moment(
+        mut self,
+        value: Option<DateTimeAsMicroseconds>,
+    ) -> Self {
+        match &mut self {
+            GetEntitiesByKeysBuilder::Inner(inner) => inner.set_partition_expiration_moment(value),
+            #[cfg(feature = "mocks")]
+            GetEntitiesByKeysBuilder::Mock(inner) => inner.set_partition_expiration_moment(value),
+        }
+
+        self
+    }
+
+    pub fn set_row_expiration_moment(mut self, value: Option<DateTimeAsMicroseconds>) -> Self {
+        match &mut self {
+            GetEntitiesByKeysBuilder::Inner(inner) => inner.set_row_expiration_moment(value),
+            #[cfg(feature = "mocks")]
+            GetEntitiesByKeysBuilder::Mock(inner) => inner.set_row_expiration_moment(value),
+        }
+
+        self
+    }
+
+    pub async fn execute(&self) -> Vec<Arc<TMyNoSqlEntity>> {
+        match self {
+            GetEntitiesByKeysBuilder::Inner(inner) => inner.execute().await,
+            #[cfg(feature = "mocks")]
+            GetEntitiesByKeysBuilder::Mock(inner) => inner.execute().await,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::super::{my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner, update_statistics};
+
+pub struct GetEntitiesByKeysBuilderInner<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
+{
+    keys: &'s [(&'s str, &'s str)],
+    update_statistic_data: UpdateEntityStatisticsData,
+    inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>,
+}
+
+impl<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
+    GetEntitiesByKeysBuilderInner<'s, TMyNoSqlEntity>
+{
+    pub fn new(
+        keys: &'s [(&'s str, &'s str)],
+        inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>,
+    ) -> Self {
+        Self {
+            keys,
+            update_statistic_data: UpdateEntityStatisticsData::default(),
+            inner,
+        }
+    }
+
+    pub fn set_partition_last_read_moment(&mut self) {
+        self.update_statistic_data.partition_last_read_moment = true;
+    }
+
+    pub fn set_row_last_read_moment(&mut self) {
+        self.update_statistic_data.row_last_read_moment = true;
+    }
+
+    pub fn set_partition_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.partition_expiration_moment = Some(value);
+    }
+
+    pub fn set_row_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.row_expiration_moment = Some(value);
+    }
+
+    pub async fn execute(&self) -> Vec<Arc<TMyNoSqlEntity>> {
+        let result = self.inner.get_entities().get_entities_by_keys(self.keys);
+
+        update_statistics::update_statistics(
+            self.inner.as_ref(),
+            &result,
+            &self.update_statistic_data,
+        )
+        .await;
+
+        result
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::subscribers::MyNoSqlDataReaderMockInner;
+
+pub struct GetEntitiesByKeysBuilderMock<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    keys: &'s [(&'s str, &'s str)],
+    update_statistic_data: UpdateEntityStatisticsData,
+    inner: Arc<MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
+}
+
+impl<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
+    GetEntitiesByKeysBuilderMock<'s, TMyNoSqlEntity>
+{
+    pub fn new(
+        keys: &'s [(&'s str, &'s str)],
+        inner: Arc<MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
+    ) -> Self {
+        Self {
+            keys,
+            update_statistic_data: UpdateEntityStatisticsData::default(),
+            inner,
+        }
+    }
+
+    pub fn set_partition_last_read_moment(&mut self) {
+        self.update_statistic_data.partition_last_read_moment = true;
+    }
+
+    pub fn set_row_last_read_moment(&mut self) {
+        self.update_statistic_data.row_last_read_moment = true;
+    }
+
+    pub fn set_partition_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.partition_expiration_moment = Some(value);
+    }
+
+    pub fn set_row_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.row_expiration_moment = Some(value);
+    }
+
+    // Mock does not notify the server, the requested statistics can be checked by the test instead
+    pub fn get_update_statistic_data(&self) -> &UpdateEntityStatisticsData {
+        &self.update_statistic_data
+    }
+
+    pub async fn execute(&self) -> Vec<Arc<TMyNoSqlEntity>> {
+        self.inner.get_entities_by_keys(self.keys)
+    }
+}
@@ -0,0 +1,8 @@
+mod get_entities_by_keys_builder;
+pub use get_entities_by_keys_builder::*;
+mod get_entities_by_keys_builder_inner;
+pub use get_entities_by_keys_builder_inner::*;
+#[cfg(feature = "mocks")]
+mod get_entities_by_keys_builder_mock;
+#[cfg(feature = "mocks")]
+pub use get_entities_by_keys_builder_mock::*;
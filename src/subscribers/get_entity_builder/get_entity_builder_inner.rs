@@ -46,10 +46,10 @@ impl<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
     }
 
     pub async fn execute(&self) -> Option<Arc<TMyNoSqlEntity>> {
-        let result = {
-            let reader = self.inner.get_data().read().await;
-            reader.get_entity(self.partition_key, self.row_key)
-        };
+        let result = self
+            .inner
+            .get_entities()
+            .get_entity(self.partition_key, self.row_key);
 
         if result.is_some() {
             self.inner
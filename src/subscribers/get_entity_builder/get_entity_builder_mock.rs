@@ -53,8 +53,6 @@ impl<'s, TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>
     }
 
     pub async fn execute(&self) -> Option<Arc<TMyNoSqlEntity>> {
-        self.inner
-            .get_entity(self.partition_key, self.row_key)
-            .await
+        self.inner.get_entity(self.partition_key, self.row_key)
     }
 }
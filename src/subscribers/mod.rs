@@ -1,22 +1,58 @@
+mod broken_entities;
 mod callback_triggers;
+mod callbacks_dead_letter;
+mod callbacks_retry_policy;
 mod get_entities_builder;
+mod get_entities_by_keys_builder;
 mod get_entity_builder;
 mod my_no_sql_data_reader;
 mod my_no_sql_data_reader_callbacks;
 mod my_no_sql_data_reader_callbacks_pusher;
+mod my_no_sql_data_reader_callbacks_queue;
+mod my_no_sql_data_reader_changes;
 mod my_no_sql_data_reader_data;
+mod my_no_sql_data_reader_entities;
+mod my_no_sql_data_reader_extended_callbacks;
+mod my_no_sql_data_reader_indexes;
+mod my_no_sql_data_reader_status;
+mod my_no_sql_data_reader_sync;
 mod my_no_sql_data_reader_tcp;
+mod my_no_sql_data_reader_view;
+mod my_no_sql_read_view;
+mod query_builder;
 mod subscribers;
+#[cfg(test)]
+mod test_row;
+mod unchanged_rows_policy;
 mod update_event_trait;
+mod update_statistics;
 pub use my_no_sql_data_reader_data::MyNoSqlDataReaderData;
+pub use my_no_sql_data_reader_entities::*;
+pub use my_no_sql_data_reader_extended_callbacks::{
+    CallbackResult, MyNoSqlDataReaderExtendedCallBacks,
+};
+pub use my_no_sql_data_reader_indexes::*;
 pub use my_no_sql_data_reader_tcp::MyNoSqlDataReaderTcp;
+pub use my_no_sql_data_reader_view::MyNoSqlDataReaderView;
+pub use my_no_sql_read_view::MyNoSqlReadView;
 
+pub use broken_entities::*;
+pub use callbacks_dead_letter::*;
+pub use callbacks_retry_policy::CallbacksRetryPolicy;
 pub use get_entities_builder::*;
+pub use get_entities_by_keys_builder::*;
 pub use get_entity_builder::*;
 pub use my_no_sql_data_reader::*;
 pub use my_no_sql_data_reader_callbacks::MyNoSqlDataReaderCallBacks;
 pub use my_no_sql_data_reader_callbacks_pusher::MyNoSqlDataReaderCallBacksPusher;
-pub use subscribers::Subscribers;
+pub use my_no_sql_data_reader_callbacks_queue::{CallbacksOverflowPolicy, CallbacksQueueMetrics};
+pub use my_no_sql_data_reader_changes::*;
+pub use my_no_sql_data_reader_status::*;
+pub use my_no_sql_data_reader_sync::*;
+pub use query_builder::*;
+pub(crate) use subscribers::RemovedSubscriber;
+pub use subscribers::{RemoveReaderResult, Subscribers};
+pub use unchanged_rows_policy::UnchangedRowsPolicy;
 pub use update_event_trait::UpdateEvent;
 #[cfg(feature = "mocks")]
 mod my_no_sql_data_reader_mock;
@@ -2,7 +2,10 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 
-use super::{GetEntitiesBuilder, GetEntityBuilder};
+use super::{
+    GetEntitiesBuilder, GetEntitiesByKeysBuilder, GetEntityBuilder, MyNoSqlPartitionSnapshot,
+    QueryBuilder,
+};
 
 #[async_trait::async_trait]
 pub trait MyNoSqlDataReader<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
@@ -29,4 +32,18 @@ pub trait MyNoSqlDataReader<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'stati
     ) -> GetEntityBuilder<TMyNoSqlEntity>;
 
     async fn has_partition(&self, partition_key: &str) -> bool;
+
+    async fn get_entities_by_keys(&self, keys: &[(&str, &str)]) -> Vec<Arc<TMyNoSqlEntity>>;
+
+    async fn get_partitions(
+        &self,
+        partition_keys: &[&str],
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>>;
+
+    fn get_entities_by_keys_with_callback_to_server<'s>(
+        &'s self,
+        keys: &'s [(&'s str, &'s str)],
+    ) -> GetEntitiesByKeysBuilder<'s, TMyNoSqlEntity>;
+
+    fn query(&self) -> QueryBuilder<TMyNoSqlEntity>;
 }
@@ -1,118 +1,996 @@
-use std::sync::Arc;
+use std::{
+    any::Any,
+    collections::VecDeque,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    task::Poll,
+};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
-use rust_extensions::{
-    events_loop::{EventsLoop, EventsLoopTick},
-    ApplicationStates,
-};
+use rust_extensions::ApplicationStates;
 
-use super::MyNoSqlDataReaderCallBacks;
+use super::{
+    callback_triggers::CallbackTriggersTarget,
+    my_no_sql_data_reader_callbacks_queue::CallbacksQueue, CallbackError, CallbackEvent,
+    CallbackResult, CallbacksDeadLetter, CallbacksDeadLetterHandler, CallbacksOverflowPolicy,
+    CallbacksQueueMetrics, CallbacksRetryPolicy, LogCallbacksDeadLetterHandler,
+    MyNoSqlDataReaderExtendedCallBacks, MyNoSqlReadView,
+};
 
 pub enum PusherEvents<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
-    InsertedOrReplaced(String, Vec<Arc<TMyNoSqlEntity>>),
+    Inserted(String, Vec<Arc<TMyNoSqlEntity>>),
+    Updated(String, Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>),
     Deleted(String, Vec<Arc<TMyNoSqlEntity>>),
+    // Rows deleted by the coalesced batches are kept, since the view does not have them anymore
+    ResyncPartition(String, Vec<Arc<TMyNoSqlEntity>>),
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> Clone for PusherEvents<TMyNoSqlEntity> {
+    fn clone(&self) -> Self {
+        match self {
+            PusherEvents::Inserted(partition_key, entities) => {
+                PusherEvents::Inserted(partition_key.clone(), entities.clone())
+            }
+            PusherEvents::Updated(partition_key, entities) => {
+                PusherEvents::Updated(partition_key.clone(), entities.clone())
+            }
+            PusherEvents::Deleted(partition_key, entities) => {
+                PusherEvents::Deleted(partition_key.clone(), entities.clone())
+            }
+            PusherEvents::ResyncPartition(partition_key, deleted) => {
+                PusherEvents::ResyncPartition(partition_key.clone(), deleted.clone())
+            }
+        }
+    }
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> PusherEvents<TMyNoSqlEntity> {
+    pub fn len(&self) -> usize {
+        match self {
+            PusherEvents::Inserted(_, entities) => entities.len(),
+            PusherEvents::Updated(_, entities) => entities.len(),
+            PusherEvents::Deleted(_, entities) => entities.len(),
+            PusherEvents::ResyncPartition(_, _) => 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_partition_key(&self) -> &str {
+        match self {
+            PusherEvents::Inserted(partition_key, _) => partition_key,
+            PusherEvents::Updated(partition_key, _) => partition_key,
+            PusherEvents::Deleted(partition_key, _) => partition_key,
+            PusherEvents::ResyncPartition(partition_key, _) => partition_key,
+        }
+    }
+
+    // Splits off the tail of the event so the event keeps at most max_rows rows
+    fn split_off(&mut self, max_rows: usize) -> Option<Self> {
+        if self.len() <= max_rows {
+            return None;
+        }
+
+        let result = match self {
+            PusherEvents::Inserted(partition_key, entities) => {
+                PusherEvents::Inserted(partition_key.clone(), entities.split_off(max_rows))
+            }
+            PusherEvents::Updated(partition_key, entities) => {
+                PusherEvents::Updated(partition_key.clone(), entities.split_off(max_rows))
+            }
+            PusherEvents::Deleted(partition_key, entities) => {
+                PusherEvents::Deleted(partition_key.clone(), entities.split_off(max_rows))
+            }
+            PusherEvents::ResyncPartition(_, _) => return None,
+        };
+
+        Some(result)
+    }
+}
+
+// Events of one incoming contract are delivered to the callbacks queue as one batch
+// together with the table view right after the contract is applied
+pub struct PusherBatch<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    pub events: Vec<PusherEvents<TMyNoSqlEntity>>,
+    pub view: MyNoSqlReadView<TMyNoSqlEntity>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> Clone for PusherBatch<TMyNoSqlEntity> {
+    fn clone(&self) -> Self {
+        Self {
+            events: self.events.clone(),
+            view: self.view.clone(),
+        }
+    }
+}
+
+// Shared by all the senders, so the settings can be changed after callbacks are assigned
+struct CallbacksErrorHandling<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    retry_policy: Mutex<CallbacksRetryPolicy>,
+    dead_letter_handler:
+        Mutex<Arc<dyn CallbacksDeadLetterHandler<TMyNoSqlEntity> + Send + Sync + 'static>>,
 }
 
 pub struct MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>
 where
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
 {
-    events_loop: EventsLoop<PusherEvents<TMyNoSqlEntity>>,
+    table_name: &'static str,
+    queues: RwLock<Vec<Arc<CallbacksQueue<TMyNoSqlEntity>>>>,
+    pending: Mutex<Vec<PusherEvents<TMyNoSqlEntity>>>,
+    // Batches flushed under the reader lock and not pushed into the queues yet
+    flushed: Mutex<
+        VecDeque<(
+            PusherBatch<TMyNoSqlEntity>,
+            Vec<Arc<CallbacksQueue<TMyNoSqlEntity>>>,
+        )>,
+    >,
+    pushing: tokio::sync::Mutex<()>,
+    // 0 means batches are not limited
+    max_batch_size: AtomicUsize,
+    queue_settings: Mutex<(usize, CallbacksOverflowPolicy)>,
+    error_handling: Arc<CallbacksErrorHandling<TMyNoSqlEntity>>,
+    app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
 }
 
 impl<TMyNoSqlEntity> MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>
 where
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
 {
-    pub async fn new<
-        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
-    >(
-        callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
+    pub fn new(
+        table_name: &'static str,
         app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
     ) -> Self {
-        let events_loop_reader = MyNoSqlDataReaderCallBacksSender::new(callbacks, None);
-        let events_loop = EventsLoop::new("MyNoSqlDataReaderCallBacksPusher".to_string());
-
-        events_loop
-            .register_event_loop(Arc::new(events_loop_reader))
-            .await;
+        Self {
+            table_name,
+            queues: RwLock::new(Vec::new()),
+            pending: Mutex::new(Vec::new()),
+            flushed: Mutex::new(VecDeque::new()),
+            pushing: tokio::sync::Mutex::new(()),
+            max_batch_size: AtomicUsize::new(0),
+            queue_settings: Mutex::new((0, CallbacksOverflowPolicy::default())),
+            error_handling: Arc::new(CallbacksErrorHandling {
+                retry_policy: Mutex::new(CallbacksRetryPolicy::default()),
+                dead_letter_handler: Mutex::new(Arc::new(LogCallbacksDeadLetterHandler)),
+            }),
+            app_states,
+        }
+    }
 
-        events_loop
-            .start(app_states, my_logger::LOGGER.clone())
-            .await;
-        Self { events_loop }
+    pub fn has_callbacks(&self) -> bool {
+        !self.queues.read().unwrap().is_empty()
     }
 
-    pub fn inserted_or_replaced(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
-        self.events_loop.send(PusherEvents::InsertedOrReplaced(
-            partition_key.to_string(),
-            entities,
+    // Replay view is pushed into the queue of the new callbacks before they start to receive updates.
+    // Replay ignores the capacity of the queue, so no row of it is dropped or coalesced.
+    // Must be called under the same lock as flush, so no update is missed or delivered twice
+    pub fn add_callbacks<
+        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
+    >(
+        &self,
+        callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
+        replay_view: Option<MyNoSqlReadView<TMyNoSqlEntity>>,
+    ) {
+        let (capacity, overflow_policy) = *self.queue_settings.lock().unwrap();
+        let queue = Arc::new(CallbacksQueue::new(
+            self.table_name,
+            capacity,
+            overflow_policy,
         ));
+
+        let sender = MyNoSqlDataReaderCallBacksSender::new(
+            self.table_name,
+            callbacks,
+            self.error_handling.clone(),
+            queue.clone(),
+        );
+        let app_states = self.app_states.clone();
+        let consumer_queue = queue.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let batch = match consumer_queue.pop().await {
+                    Some(batch) => batch,
+                    None => break,
+                };
+
+                if app_states.is_shutting_down() {
+                    break;
+                }
+
+                sender.deliver(batch).await;
+            }
+        });
+
+        if let Some(replay_view) = replay_view {
+            let max_batch_size = self.max_batch_size.load(Ordering::Relaxed);
+
+            for batch in get_replay_batches(replay_view, max_batch_size) {
+                queue.push_replay(batch);
+            }
+        }
+
+        self.queues.write().unwrap().push(queue);
+    }
+
+    pub fn set_max_batch_size(&self, value: usize) {
+        self.max_batch_size.store(value, Ordering::Relaxed);
+    }
+
+    // 0 capacity means queue is not limited
+    pub fn set_queue_capacity(&self, capacity: usize, overflow_policy: CallbacksOverflowPolicy) {
+        *self.queue_settings.lock().unwrap() = (capacity, overflow_policy);
+
+        for queue in self.queues.read().unwrap().iter() {
+            queue.set_capacity(capacity, overflow_policy);
+        }
+    }
+
+    pub fn set_retry_policy(&self, retry_policy: CallbacksRetryPolicy) {
+        *self.error_handling.retry_policy.lock().unwrap() = retry_policy;
+    }
+
+    pub fn set_dead_letter_handler(
+        &self,
+        handler: Arc<dyn CallbacksDeadLetterHandler<TMyNoSqlEntity> + Send + Sync + 'static>,
+    ) {
+        *self.error_handling.dead_letter_handler.lock().unwrap() = handler;
+    }
+
+    // One item per assigned callback in the order of assignment
+    pub fn get_queue_metrics(&self) -> Vec<CallbacksQueueMetrics> {
+        self.queues
+            .read()
+            .unwrap()
+            .iter()
+            .map(|queue| queue.get_metrics())
+            .collect()
+    }
+
+    fn send(&self, event: PusherEvents<TMyNoSqlEntity>) {
+        if event.is_empty() {
+            return;
+        }
+
+        self.pending.lock().unwrap().push(event);
+    }
+
+    // Must be called under the reader lock once the incoming contract is applied,
+    // with the view of the updated table. Batches are pushed into the queues by push_flushed
+    pub fn flush(&self, view: MyNoSqlReadView<TMyNoSqlEntity>) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+
+        if pending.is_empty() {
+            return;
+        }
+
+        let queues = self.queues.read().unwrap().clone();
+
+        let max_batch_size = self.max_batch_size.load(Ordering::Relaxed);
+
+        let mut flushed = self.flushed.lock().unwrap();
+
+        for batch in split_into_batches(pending, max_batch_size, view) {
+            flushed.push_back((batch, queues.clone()));
+        }
+    }
+
+    // Must be called after the reader lock is released, so a callback which reads the reader
+    // does not deadlock. Batches are pushed in the order they were flushed.
+    // With CallbacksOverflowPolicy::Block it waits while any of the queues is full
+    pub async fn push_flushed(&self) {
+        if self.flushed.lock().unwrap().is_empty() {
+            return;
+        }
+
+        let _pushing = self.pushing.lock().await;
+
+        loop {
+            let next = self.flushed.lock().unwrap().pop_front();
+
+            let (batch, queues) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            for queue in queues.iter() {
+                queue.push(batch.clone()).await;
+            }
+        }
+    }
+
+    pub fn inserted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
+        self.send(PusherEvents::Inserted(partition_key.to_string(), entities));
+    }
+
+    pub fn updated(
+        &self,
+        partition_key: &str,
+        entities: Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>,
+    ) {
+        self.send(PusherEvents::Updated(partition_key.to_string(), entities));
     }
 
     pub fn deleted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
-        self.events_loop
-            .send(PusherEvents::Deleted(partition_key.to_string(), entities));
+        self.send(PusherEvents::Deleted(partition_key.to_string(), entities));
+    }
+}
+
+// Consumers stop once the reader is dropped
+impl<TMyNoSqlEntity> Drop for MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    fn drop(&mut self) {
+        for queue in self.queues.read().unwrap().iter() {
+            queue.close();
+        }
     }
 }
 
 #[async_trait::async_trait]
-impl<TMyNoSqlEntity> MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>
+impl<TMyNoSqlEntity> CallbackTriggersTarget<TMyNoSqlEntity>
     for MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>
 where
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
 {
-    async fn inserted_or_replaced(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
-        self.events_loop.send(PusherEvents::InsertedOrReplaced(
-            partition_key.to_string(),
-            entities,
-        ));
+    async fn inserted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
+        self.send(PusherEvents::Inserted(partition_key.to_string(), entities));
+    }
+
+    async fn updated(
+        &self,
+        partition_key: &str,
+        entities: Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>,
+    ) {
+        self.send(PusherEvents::Updated(partition_key.to_string(), entities));
     }
 
     async fn deleted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
-        self.events_loop
-            .send(PusherEvents::Deleted(partition_key.to_string(), entities));
+        self.send(PusherEvents::Deleted(partition_key.to_string(), entities));
+    }
+}
+
+fn split_into_batches<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static>(
+    events: Vec<PusherEvents<TMyNoSqlEntity>>,
+    max_batch_size: usize,
+    view: MyNoSqlReadView<TMyNoSqlEntity>,
+) -> Vec<PusherBatch<TMyNoSqlEntity>> {
+    if max_batch_size == 0 {
+        return vec![PusherBatch { events, view }];
+    }
+
+    let mut result = Vec::new();
+    let mut batch = Vec::new();
+    let mut batch_size = 0;
+
+    for mut event in events {
+        loop {
+            let tail = event.split_off(max_batch_size - batch_size);
+
+            batch_size += event.len();
+            batch.push(event);
+
+            if batch_size == max_batch_size {
+                result.push(PusherBatch {
+                    events: std::mem::take(&mut batch),
+                    view: view.clone(),
+                });
+                batch_size = 0;
+            }
+
+            match tail {
+                Some(tail) => event = tail,
+                None => break,
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        result.push(PusherBatch {
+            events: batch,
+            view,
+        });
+    }
+
+    result
+}
+
+fn get_replay_batches<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static>(
+    view: MyNoSqlReadView<TMyNoSqlEntity>,
+    max_batch_size: usize,
+) -> Vec<PusherBatch<TMyNoSqlEntity>> {
+    let table = match view.get_table_snapshot() {
+        Some(table) => table,
+        None => return vec![],
+    };
+
+    let events: Vec<_> = table
+        .iter()
+        .filter(|(_, partition)| !partition.is_empty())
+        .map(|(partition_key, partition)| {
+            PusherEvents::Inserted(partition_key.clone(), partition.values().cloned().collect())
+        })
+        .collect();
+
+    if events.is_empty() {
+        return vec![];
     }
+
+    split_into_batches(events, max_batch_size, view)
 }
 
 pub struct MyNoSqlDataReaderCallBacksSender<
     TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>,
+    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity>,
 > {
+    table_name: &'static str,
     callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
-    item: Option<TMyNoSqlEntity>,
+    error_handling: Arc<CallbacksErrorHandling<TMyNoSqlEntity>>,
+    // Queue the sender consumes. Retries stop while it holds the reader update
+    queue: Arc<CallbacksQueue<TMyNoSqlEntity>>,
 }
 
 impl<
         TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
+        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
     > MyNoSqlDataReaderCallBacksSender<TMyNoSqlEntity, TMyNoSqlDataReaderCallBacks>
 {
-    pub fn new(callbacks: Arc<TMyNoSqlDataReaderCallBacks>, item: Option<TMyNoSqlEntity>) -> Self {
-        Self { callbacks, item }
+    fn new(
+        table_name: &'static str,
+        callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
+        error_handling: Arc<CallbacksErrorHandling<TMyNoSqlEntity>>,
+        queue: Arc<CallbacksQueue<TMyNoSqlEntity>>,
+    ) -> Self {
+        Self {
+            table_name,
+            callbacks,
+            error_handling,
+            queue,
+        }
     }
-}
 
-#[async_trait::async_trait]
-impl<
-        TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
-        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
-    > EventsLoopTick<PusherEvents<TMyNoSqlEntity>>
-    for MyNoSqlDataReaderCallBacksSender<TMyNoSqlEntity, TMyNoSqlDataReaderCallBacks>
-{
-    async fn tick(&self, model: PusherEvents<TMyNoSqlEntity>) {
-        match model {
-            PusherEvents::InsertedOrReplaced(partition_key, entities) => {
-                self.callbacks
-                    .inserted_or_replaced(partition_key.as_str(), entities)
+    pub async fn deliver(&self, batch: PusherBatch<TMyNoSqlEntity>) {
+        let view = batch.view;
+
+        for event in batch.events {
+            let event = match event {
+                PusherEvents::Inserted(partition_key, entities) => {
+                    CallbackEvent::Inserted(partition_key, entities)
+                }
+                PusherEvents::Updated(partition_key, entities) => {
+                    CallbackEvent::Updated(partition_key, entities)
+                }
+                PusherEvents::Deleted(partition_key, entities) => {
+                    CallbackEvent::Deleted(partition_key, entities)
+                }
+                PusherEvents::ResyncPartition(partition_key, deleted) => {
+                    // Row could be deleted and inserted again by the coalesced batches
+                    let deleted: Vec<_> = deleted
+                        .into_iter()
+                        .filter(|entity| {
+                            view.get_entity(partition_key.as_str(), entity.get_row_key())
+                                .is_none()
+                        })
+                        .collect();
+
+                    if !deleted.is_empty() {
+                        self.deliver_event(
+                            &view,
+                            CallbackEvent::Deleted(partition_key.clone(), deleted),
+                        )
+                        .await;
+                    }
+
+                    let entities = view.get_by_partition_as_vec(partition_key.as_str());
+
+                    if entities.is_empty() {
+                        continue;
+                    }
+
+                    CallbackEvent::ResyncPartition(partition_key, entities)
+                }
+            };
+
+            self.deliver_event(&view, event).await;
+        }
+    }
+
+    async fn deliver_event(
+        &self,
+        view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        event: CallbackEvent<TMyNoSqlEntity>,
+    ) {
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let error = match catch_unwind(self.invoke(view, event.clone())).await {
+                Ok(Ok(())) => return,
+                Ok(Err(err)) => CallbackError::Failed(err.to_string()),
+                Err(panic_message) => CallbackError::Panicked(panic_message),
+            };
+
+            let delay = self
+                .error_handling
+                .retry_policy
+                .lock()
+                .unwrap()
+                .get_delay(attempts);
+
+            // With CallbacksOverflowPolicy::Block the retries would stall the reader update,
+            // so the event goes to the dead letter handler instead
+            let give_up = match delay {
+                Some(delay) if !self.queue.is_writer_blocked() => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = self.queue.wait_writer_blocked() => {}
+                    }
+
+                    self.queue.is_writer_blocked()
+                }
+                _ => true,
+            };
+
+            if give_up {
+                let handler = self
+                    .error_handling
+                    .dead_letter_handler
+                    .lock()
+                    .unwrap()
+                    .clone();
+
+                handler
+                    .dead_letter(CallbacksDeadLetter {
+                        table_name: self.table_name,
+                        version: view.version,
+                        event,
+                        error,
+                        attempts,
+                    })
                     .await;
+
+                return;
             }
-            PusherEvents::Deleted(partition_key, entities) => {
+        }
+    }
+
+    async fn invoke(
+        &self,
+        view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        event: CallbackEvent<TMyNoSqlEntity>,
+    ) -> CallbackResult {
+        match event {
+            CallbackEvent::Inserted(partition_key, entities) => {
                 self.callbacks
-                    .deleted(partition_key.as_str(), entities)
-                    .await;
+                    .inserted(view, partition_key.as_str(), entities)
+                    .await
+            }
+            CallbackEvent::Updated(partition_key, entities) => {
+                self.callbacks
+                    .updated(view, partition_key.as_str(), entities)
+                    .await
+            }
+            CallbackEvent::Deleted(partition_key, entities) => {
+                self.callbacks
+                    .deleted(view, partition_key.as_str(), entities)
+                    .await
+            }
+            CallbackEvent::ResyncPartition(partition_key, entities) => {
+                self.callbacks
+                    .resync_partition(view, partition_key.as_str(), entities)
+                    .await
             }
         }
-        if self.item.is_some() {}
+    }
+}
+
+// Panic inside of the callback must not stop the delivery to this callback
+async fn catch_unwind<TResult>(future: impl Future<Output = TResult>) -> Result<TResult, String> {
+    let mut future = Box::pin(future);
+
+    std::future::poll_fn(move |cx| {
+        match std::panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(Poll::Ready(result)) => Poll::Ready(Ok(result)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(panic) => Poll::Ready(Err(get_panic_message(panic))),
+        }
+    })
+    .await
+}
+
+fn get_panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+
+    if let Some(message) = panic.downcast_ref::<String>() {
+        return message.clone();
+    }
+
+    "Unknown panic".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use super::{
+        CallbacksErrorHandling, CallbacksQueue, MyNoSqlDataReaderCallBacksSender, PusherBatch,
+        PusherEvents,
+    };
+    use crate::{
+        subscribers::{
+            test_row::TestRow, CallbackError, CallbackResult, CallbacksDeadLetter,
+            CallbacksDeadLetterHandler, CallbacksOverflowPolicy, CallbacksRetryPolicy,
+            LogCallbacksDeadLetterHandler, MyNoSqlDataReaderExtendedCallBacks, MyNoSqlReadView,
+            MyNoSqlTableEntities,
+        },
+        ReconnectBackoff,
+    };
+
+    fn to_partition(rows: Vec<Arc<TestRow>>) -> BTreeMap<String, Arc<TestRow>> {
+        rows.into_iter()
+            .map(|row| (row.row_key.clone(), row))
+            .collect()
+    }
+
+    fn create_rows(amount: usize) -> Vec<Arc<TestRow>> {
+        (0..amount)
+            .map(|i| Arc::new(TestRow::new("PK".to_string(), i.to_string(), 0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_events_are_split_by_max_batch_size() {
+        let events = vec![
+            PusherEvents::Inserted("PK1".to_string(), create_rows(3)),
+            PusherEvents::Deleted("PK2".to_string(), create_rows(4)),
+        ];
+
+        let batches = super::split_into_batches(events, 5, MyNoSqlReadView::new(1, None));
+
+        let sizes: Vec<usize> = batches
+            .iter()
+            .map(|batch| batch.events.iter().map(|event| event.len()).sum())
+            .collect();
+
+        assert_eq!(vec![5, 2], sizes);
+        assert_eq!(2, batches[0].events.len());
+    }
+
+    #[test]
+    fn test_not_limited_batch() {
+        let events = vec![
+            PusherEvents::Inserted("PK1".to_string(), create_rows(3)),
+            PusherEvents::Deleted("PK2".to_string(), create_rows(4)),
+        ];
+
+        let batches = super::split_into_batches(events, 0, MyNoSqlReadView::new(1, None));
+
+        assert_eq!(1, batches.len());
+        assert_eq!(2, batches[0].events.len());
+    }
+
+    #[test]
+    fn test_replay_batches_carry_whole_table() {
+        let mut table = MyNoSqlTableEntities::new();
+        table.insert("PK1".to_string(), Arc::new(to_partition(create_rows(3))));
+        table.insert("PK2".to_string(), Arc::new(BTreeMap::new()));
+        table.insert("PK3".to_string(), Arc::new(to_partition(create_rows(2))));
+
+        let view = MyNoSqlReadView::new(5, Some(Arc::new(table)));
+
+        let batches = super::get_replay_batches(view, 4);
+
+        assert_eq!(2, batches.len());
+        assert_eq!(5, batches[0].view.version);
+
+        let partition_keys: Vec<&str> = batches
+            .iter()
+            .flat_map(|batch| batch.events.iter())
+            .map(|event| event.get_partition_key())
+            .collect();
+
+        assert_eq!(vec!["PK1", "PK3", "PK3"], partition_keys);
+        assert!(super::get_replay_batches(MyNoSqlReadView::<TestRow>::new(0, None), 4).is_empty());
+    }
+
+    // Panics on the first call and fails on every next one
+    struct FailingCallbacks {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl MyNoSqlDataReaderExtendedCallBacks<TestRow> for FailingCallbacks {
+        async fn inserted(
+            &self,
+            _: &MyNoSqlReadView<TestRow>,
+            _: &str,
+            _: Vec<Arc<TestRow>>,
+        ) -> CallbackResult {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("First call");
+            }
+
+            Err("Failed".into())
+        }
+
+        async fn deleted(
+            &self,
+            _: &MyNoSqlReadView<TestRow>,
+            _: &str,
+            _: Vec<Arc<TestRow>>,
+        ) -> CallbackResult {
+            Ok(())
+        }
+    }
+
+    struct TestDeadLetterHandler {
+        dead_letters: Mutex<Vec<CallbacksDeadLetter<TestRow>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CallbacksDeadLetterHandler<TestRow> for TestDeadLetterHandler {
+        async fn dead_letter(&self, dead_letter: CallbacksDeadLetter<TestRow>) {
+            self.dead_letters.lock().unwrap().push(dead_letter);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_event_is_retried_and_dead_lettered() {
+        let callbacks = Arc::new(FailingCallbacks {
+            calls: AtomicUsize::new(0),
+        });
+
+        let dead_letter_handler = Arc::new(TestDeadLetterHandler {
+            dead_letters: Mutex::new(Vec::new()),
+        });
+
+        let retry_policy = CallbacksRetryPolicy::new(
+            3,
+            ReconnectBackoff::new(Duration::from_millis(1), Duration::from_millis(1)),
+        );
+
+        let sender = MyNoSqlDataReaderCallBacksSender::new(
+            "Test",
+            callbacks.clone(),
+            Arc::new(CallbacksErrorHandling {
+                retry_policy: Mutex::new(retry_policy),
+                dead_letter_handler: Mutex::new(dead_letter_handler.clone()),
+            }),
+            Arc::new(CallbacksQueue::new(
+                "Test",
+                0,
+                CallbacksOverflowPolicy::Block,
+            )),
+        );
+
+        sender
+            .deliver(PusherBatch {
+                events: vec![
+                    PusherEvents::Inserted("PK".to_string(), create_rows(2)),
+                    PusherEvents::Deleted("PK".to_string(), create_rows(1)),
+                ],
+                view: MyNoSqlReadView::new(7, None),
+            })
+            .await;
+
+        assert_eq!(3, callbacks.calls.load(Ordering::SeqCst));
+
+        let dead_letters = dead_letter_handler.dead_letters.lock().unwrap();
+        assert_eq!(1, dead_letters.len());
+        assert_eq!(3, dead_letters[0].attempts);
+        assert_eq!(7, dead_letters[0].version);
+        assert_eq!("PK", dead_letters[0].event.get_partition_key());
+        assert!(matches!(dead_letters[0].error, CallbackError::Failed(_)));
+    }
+
+    // Records the row keys of every call
+    struct RecordingCallbacks {
+        calls: Mutex<Vec<(&'static str, Vec<String>)>>,
+    }
+
+    impl RecordingCallbacks {
+        fn record(&self, method: &'static str, entities: Vec<Arc<TestRow>>) {
+            let row_keys = entities.iter().map(|row| row.row_key.clone()).collect();
+            self.calls.lock().unwrap().push((method, row_keys));
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MyNoSqlDataReaderExtendedCallBacks<TestRow> for RecordingCallbacks {
+        async fn inserted(
+            &self,
+            _: &MyNoSqlReadView<TestRow>,
+            _: &str,
+            entities: Vec<Arc<TestRow>>,
+        ) -> CallbackResult {
+            self.record("inserted", entities);
+            Ok(())
+        }
+
+        async fn deleted(
+            &self,
+            _: &MyNoSqlReadView<TestRow>,
+            _: &str,
+            entities: Vec<Arc<TestRow>>,
+        ) -> CallbackResult {
+            self.record("deleted", entities);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resync_delivers_deleted_rows() {
+        let callbacks = Arc::new(RecordingCallbacks {
+            calls: Mutex::new(Vec::new()),
+        });
+
+        let sender = MyNoSqlDataReaderCallBacksSender::new(
+            "Test",
+            callbacks.clone(),
+            Arc::new(CallbacksErrorHandling {
+                retry_policy: Mutex::new(CallbacksRetryPolicy::default()),
+                dead_letter_handler: Mutex::new(Arc::new(LogCallbacksDeadLetterHandler)),
+            }),
+            Arc::new(CallbacksQueue::new(
+                "Test",
+                0,
+                CallbacksOverflowPolicy::Block,
+            )),
+        );
+
+        // Row 1 is deleted and inserted again, row 0 is deleted, PK2 is deleted completely
+        let mut table = MyNoSqlTableEntities::new();
+        table.insert(
+            "PK".to_string(),
+            Arc::new(to_partition(vec![create_rows(2)[1].clone()])),
+        );
+
+        sender
+            .deliver(PusherBatch {
+                events: vec![
+                    PusherEvents::ResyncPartition("PK".to_string(), create_rows(2)),
+                    PusherEvents::ResyncPartition("PK2".to_string(), create_rows(1)),
+                ],
+                view: MyNoSqlReadView::new(1, Some(Arc::new(table))),
+            })
+            .await;
+
+        let calls = callbacks.calls.lock().unwrap();
+        assert_eq!(
+            vec![
+                ("deleted", vec!["0".to_string()]),
+                ("inserted", vec!["1".to_string()]),
+                ("deleted", vec!["0".to_string()]),
+            ],
+            *calls
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_while_reader_is_blocked() {
+        let callbacks = Arc::new(FailingCallbacks {
+            calls: AtomicUsize::new(0),
+        });
+
+        let dead_letter_handler = Arc::new(TestDeadLetterHandler {
+            dead_letters: Mutex::new(Vec::new()),
+        });
+
+        let queue = Arc::new(CallbacksQueue::new(
+            "Test",
+            1,
+            CallbacksOverflowPolicy::Block,
+        ));
+
+        let sender = MyNoSqlDataReaderCallBacksSender::new(
+            "Test",
+            callbacks.clone(),
+            Arc::new(CallbacksErrorHandling {
+                retry_policy: Mutex::new(CallbacksRetryPolicy::new(
+                    10,
+                    ReconnectBackoff::new(Duration::from_secs(60), Duration::from_secs(60)),
+                )),
+                dead_letter_handler: Mutex::new(dead_letter_handler.clone()),
+            }),
+            queue.clone(),
+        );
+
+        let create_batch = || PusherBatch {
+            events: vec![PusherEvents::Inserted("PK".to_string(), create_rows(1))],
+            view: MyNoSqlReadView::new(1, None),
+        };
+
+        queue.push(create_batch()).await;
+        let batch = queue.pop().await.unwrap();
+        queue.push(create_batch()).await;
+
+        // Queue is full, so the writer waits until the batch is delivered
+        let writer_queue = queue.clone();
+        let writer = tokio::spawn(async move { writer_queue.push(create_batch()).await });
+
+        tokio::time::timeout(Duration::from_secs(5), sender.deliver(batch))
+            .await
+            .unwrap();
+
+        queue.pop().await.unwrap();
+        writer.await.unwrap();
+
+        let dead_letters = dead_letter_handler.dead_letters.lock().unwrap();
+        assert_eq!(1, dead_letters.len());
+        assert!(dead_letters[0].attempts < 10);
+    }
+
+    #[tokio::test]
+    async fn test_retries_wait_after_reader_was_blocked_once() {
+        let callbacks = Arc::new(FailingCallbacks {
+            calls: AtomicUsize::new(0),
+        });
+
+        let dead_letter_handler = Arc::new(TestDeadLetterHandler {
+            dead_letters: Mutex::new(Vec::new()),
+        });
+
+        let queue = Arc::new(CallbacksQueue::new(
+            "Test",
+            1,
+            CallbacksOverflowPolicy::Block,
+        ));
+
+        let sender = MyNoSqlDataReaderCallBacksSender::new(
+            "Test",
+            callbacks.clone(),
+            Arc::new(CallbacksErrorHandling {
+                retry_policy: Mutex::new(CallbacksRetryPolicy::new(
+                    3,
+                    ReconnectBackoff::new(Duration::from_millis(100), Duration::from_millis(100)),
+                )),
+                dead_letter_handler: Mutex::new(dead_letter_handler.clone()),
+            }),
+            queue.clone(),
+        );
+
+        let create_batch = || PusherBatch {
+            events: vec![PusherEvents::Inserted("PK".to_string(), create_rows(1))],
+            view: MyNoSqlReadView::new(1, None),
+        };
+
+        // Writer gets blocked once while nobody waits for it
+        queue.push(create_batch()).await;
+        let writer_queue = queue.clone();
+        let writer = tokio::spawn(async move { writer_queue.push(create_batch()).await });
+
+        while !queue.is_writer_blocked() {
+            tokio::task::yield_now().await;
+        }
+
+        let batch = queue.pop().await.unwrap();
+        writer.await.unwrap();
+        assert!(!queue.is_writer_blocked());
+
+        let started = std::time::Instant::now();
+        sender.deliver(batch).await;
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        let dead_letters = dead_letter_handler.dead_letters.lock().unwrap();
+        assert_eq!(1, dead_letters.len());
+        assert_eq!(3, dead_letters[0].attempts);
     }
 }
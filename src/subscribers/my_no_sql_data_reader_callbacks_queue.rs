@@ -0,0 +1,418 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use tokio::sync::Notify;
+
+use super::my_no_sql_data_reader_callbacks_pusher::{PusherBatch, PusherEvents};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CallbacksOverflowPolicy {
+    // Reader update waits until the callbacks consumer frees the space. The connection stops
+    // reading while it waits, so every table of the connection waits for the slowest callback
+    #[default]
+    Block,
+    // First dropped batch is written to the log, the rest are only counted in the metrics
+    DropOldest,
+    // Queued batches are replaced with one resync event per touched partition.
+    // Resync event keeps the rows deleted by the replaced batches
+    CoalesceToResync,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CallbacksQueueMetrics {
+    pub queue_depth: usize,
+    // 0 means queue is not limited
+    pub capacity: usize,
+    pub dropped_batches: usize,
+    pub coalesced_batches: usize,
+}
+
+struct CallbacksQueueState<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    items: VecDeque<PusherBatch<TMyNoSqlEntity>>,
+    capacity: usize,
+    overflow_policy: CallbacksOverflowPolicy,
+    dropped_batches: usize,
+    coalesced_batches: usize,
+    // Replay batches are at the front of the queue. They do not count against the capacity
+    // and are never dropped or coalesced
+    replay_batches: usize,
+    // Reader update waits for the space with CallbacksOverflowPolicy::Block
+    writer_blocked: bool,
+    closed: bool,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> CallbacksQueueState<TMyNoSqlEntity> {
+    fn has_space(&self) -> bool {
+        self.capacity == 0 || self.items.len() - self.replay_batches < self.capacity
+    }
+
+    // Resync events are delivered with the view of the newest batch
+    fn coalesce(&mut self, batch: PusherBatch<TMyNoSqlEntity>) {
+        let view = batch.view.clone();
+        let mut partitions: BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>> =
+            BTreeMap::new();
+
+        for queued in self
+            .items
+            .drain(self.replay_batches..)
+            .chain(std::iter::once(batch))
+        {
+            for event in queued.events {
+                let deleted = partitions
+                    .entry(event.get_partition_key().to_string())
+                    .or_default();
+
+                match event {
+                    PusherEvents::Deleted(_, entities)
+                    | PusherEvents::ResyncPartition(_, entities) => {
+                        for entity in entities {
+                            deleted.insert(entity.get_row_key().to_string(), entity);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.coalesced_batches += 1;
+        self.items.push_back(PusherBatch {
+            events: partitions
+                .into_iter()
+                .map(|(partition_key, deleted)| {
+                    PusherEvents::ResyncPartition(partition_key, deleted.into_values().collect())
+                })
+                .collect(),
+            view,
+        });
+    }
+}
+
+pub struct CallbacksQueue<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    table_name: &'static str,
+    state: Mutex<CallbacksQueueState<TMyNoSqlEntity>>,
+    has_items: Notify,
+    has_space: Notify,
+    writer_blocked: Notify,
+}
+
+impl<TMyNoSqlEntity> CallbacksQueue<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    pub fn new(
+        table_name: &'static str,
+        capacity: usize,
+        overflow_policy: CallbacksOverflowPolicy,
+    ) -> Self {
+        Self {
+            table_name,
+            state: Mutex::new(CallbacksQueueState {
+                items: VecDeque::new(),
+                capacity,
+                overflow_policy,
+                dropped_batches: 0,
+                coalesced_batches: 0,
+                replay_batches: 0,
+                writer_blocked: false,
+                closed: false,
+            }),
+            has_items: Notify::new(),
+            has_space: Notify::new(),
+            writer_blocked: Notify::new(),
+        }
+    }
+
+    pub fn set_capacity(&self, capacity: usize, overflow_policy: CallbacksOverflowPolicy) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.capacity = capacity;
+            state.overflow_policy = overflow_policy;
+        }
+
+        self.has_space.notify_one();
+    }
+
+    // Stops the consumer once the reader is dropped. Batches pushed after that are dropped
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+
+        self.has_items.notify_one();
+        self.has_space.notify_one();
+    }
+
+    // Must be called before any other batch is pushed
+    pub fn push_replay(&self, batch: PusherBatch<TMyNoSqlEntity>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.items.push_back(batch);
+            state.replay_batches += 1;
+        }
+
+        self.has_items.notify_one();
+    }
+
+    pub async fn push(&self, batch: PusherBatch<TMyNoSqlEntity>) {
+        let mut batch = Some(batch);
+        let mut first_drop = false;
+
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if state.closed {
+                    state.writer_blocked = false;
+                    return;
+                }
+
+                if state.has_space() {
+                    state.writer_blocked = false;
+                    state.items.push_back(batch.take().unwrap());
+                    break;
+                }
+
+                match state.overflow_policy {
+                    CallbacksOverflowPolicy::Block => {
+                        state.writer_blocked = true;
+                        self.writer_blocked.notify_waiters();
+                    }
+                    CallbacksOverflowPolicy::DropOldest => {
+                        state.writer_blocked = false;
+                        let oldest = state.replay_batches;
+                        state.items.remove(oldest);
+                        state.dropped_batches += 1;
+                        first_drop = state.dropped_batches == 1;
+                        state.items.push_back(batch.take().unwrap());
+                        break;
+                    }
+                    CallbacksOverflowPolicy::CoalesceToResync => {
+                        state.writer_blocked = false;
+                        state.coalesce(batch.take().unwrap());
+                        break;
+                    }
+                }
+            }
+
+            self.has_space.notified().await;
+        }
+
+        self.has_items.notify_one();
+
+        if first_drop {
+            my_logger::LOGGER.write_error(
+                "MyNoSqlDataReader".to_string(),
+                format!(
+                    "Callbacks queue of table {} is full. The oldest batches are dropped, see dropped_batches of the queue metrics",
+                    self.table_name
+                ),
+                None,
+            );
+        }
+    }
+
+    // Returns None once the queue is closed
+    pub async fn pop(&self) -> Option<PusherBatch<TMyNoSqlEntity>> {
+        loop {
+            let item = {
+                let mut state = self.state.lock().unwrap();
+
+                if state.closed {
+                    return None;
+                }
+
+                let item = state.items.pop_front();
+
+                if item.is_some() && state.replay_batches > 0 {
+                    state.replay_batches -= 1;
+                }
+
+                item
+            };
+
+            if let Some(item) = item {
+                self.has_space.notify_one();
+                return Some(item);
+            }
+
+            self.has_items.notified().await;
+        }
+    }
+
+    pub fn is_writer_blocked(&self) -> bool {
+        self.state.lock().unwrap().writer_blocked
+    }
+
+    // Completes once a reader update starts to wait for the space in the queue.
+    // Waiter is registered before the check, so the block which happens in between is not missed
+    pub async fn wait_writer_blocked(&self) {
+        let notified = self.writer_blocked.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+
+        if self.is_writer_blocked() {
+            return;
+        }
+
+        notified.await;
+    }
+
+    pub fn get_metrics(&self) -> CallbacksQueueMetrics {
+        let state = self.state.lock().unwrap();
+
+        CallbacksQueueMetrics {
+            queue_depth: state.items.len(),
+            capacity: state.capacity,
+            dropped_batches: state.dropped_batches,
+            coalesced_batches: state.coalesced_batches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{CallbacksOverflowPolicy, CallbacksQueue};
+    use crate::subscribers::{
+        my_no_sql_data_reader_callbacks_pusher::{PusherBatch, PusherEvents},
+        test_row::TestRow,
+        MyNoSqlReadView,
+    };
+
+    fn create_batch(partition_key: &str) -> PusherBatch<TestRow> {
+        let row = Arc::new(TestRow::new(partition_key.to_string(), "RK".to_string(), 0));
+
+        PusherBatch {
+            events: vec![PusherEvents::Inserted(partition_key.to_string(), vec![row])],
+            view: MyNoSqlReadView::new(0, None),
+        }
+    }
+
+    fn get_partition_keys(batch: &PusherBatch<TestRow>) -> Vec<String> {
+        batch
+            .events
+            .iter()
+            .map(|event| event.get_partition_key().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() {
+        let queue = CallbacksQueue::new("Test", 2, CallbacksOverflowPolicy::DropOldest);
+
+        queue.push(create_batch("PK1")).await;
+        queue.push(create_batch("PK2")).await;
+        queue.push(create_batch("PK3")).await;
+
+        let metrics = queue.get_metrics();
+        assert_eq!(2, metrics.queue_depth);
+        assert_eq!(1, metrics.dropped_batches);
+
+        assert_eq!(vec!["PK2"], get_partition_keys(&queue.pop().await.unwrap()));
+        assert_eq!(vec!["PK3"], get_partition_keys(&queue.pop().await.unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_to_resync() {
+        let queue = CallbacksQueue::new("Test", 2, CallbacksOverflowPolicy::CoalesceToResync);
+
+        queue.push(create_batch("PK2")).await;
+        queue.push(create_batch("PK1")).await;
+        queue.push(create_batch("PK2")).await;
+
+        let metrics = queue.get_metrics();
+        assert_eq!(1, metrics.queue_depth);
+        assert_eq!(1, metrics.coalesced_batches);
+
+        let batch = queue.pop().await.unwrap();
+        assert_eq!(vec!["PK1", "PK2"], get_partition_keys(&batch));
+        assert!(batch
+            .events
+            .iter()
+            .all(|event| matches!(event, PusherEvents::ResyncPartition(_, _))));
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_keeps_deleted_rows() {
+        let queue = CallbacksQueue::new("Test", 1, CallbacksOverflowPolicy::CoalesceToResync);
+
+        let deleted = Arc::new(TestRow::new("PK1".to_string(), "RK1".to_string(), 0));
+
+        queue
+            .push(PusherBatch {
+                events: vec![PusherEvents::Deleted("PK1".to_string(), vec![deleted])],
+                view: MyNoSqlReadView::new(0, None),
+            })
+            .await;
+        queue.push(create_batch("PK2")).await;
+
+        let batch = queue.pop().await.unwrap();
+        assert_eq!(vec!["PK1", "PK2"], get_partition_keys(&batch));
+
+        match &batch.events[0] {
+            PusherEvents::ResyncPartition(_, deleted) => {
+                assert_eq!(1, deleted.len());
+                assert_eq!("RK1", deleted[0].row_key);
+            }
+            _ => panic!("ResyncPartition is expected"),
+        }
+
+        match &batch.events[1] {
+            PusherEvents::ResyncPartition(_, deleted) => assert!(deleted.is_empty()),
+            _ => panic!("ResyncPartition is expected"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_closed_queue_stops_consumer() {
+        let queue = Arc::new(CallbacksQueue::<TestRow>::new(
+            0,
+            CallbacksOverflowPolicy::Block,
+        ));
+
+        let consumer_queue = queue.clone();
+        let consumer = tokio::spawn(async move { consumer_queue.pop().await });
+
+        queue.close();
+
+        assert!(consumer.await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_is_not_dropped() {
+        let queue = CallbacksQueue::new("Test", 1, CallbacksOverflowPolicy::DropOldest);
+
+        queue.push_replay(create_batch("PK1"));
+        queue.push_replay(create_batch("PK2"));
+        queue.push(create_batch("PK3")).await;
+        queue.push(create_batch("PK4")).await;
+
+        let metrics = queue.get_metrics();
+        assert_eq!(3, metrics.queue_depth);
+        assert_eq!(1, metrics.dropped_batches);
+
+        for expected in ["PK1", "PK2", "PK4"] {
+            assert_eq!(
+                vec![expected],
+                get_partition_keys(&queue.pop().await.unwrap())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_is_not_coalesced() {
+        let queue = CallbacksQueue::new("Test", 1, CallbacksOverflowPolicy::CoalesceToResync);
+
+        queue.push_replay(create_batch("PK1"));
+        queue.push(create_batch("PK2")).await;
+        queue.push(create_batch("PK3")).await;
+
+        let replay = queue.pop().await.unwrap();
+        assert!(matches!(replay.events[0], PusherEvents::Inserted(_, _)));
+
+        let resync = queue.pop().await.unwrap();
+        assert_eq!(vec!["PK2", "PK3"], get_partition_keys(&resync));
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::Stream;
+
+use super::{MyNoSqlDataReaderEntities, MyNoSqlPartitionSnapshot, MyNoSqlTableSnapshot};
+
+pub const DEFAULT_CHANGES_CAPACITY: usize = 1024;
+
+pub enum ReaderChange<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    TableInitialized(MyNoSqlTableSnapshot<TMyNoSqlEntity>),
+    PartitionInitialized(String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>),
+    InsertedOrReplaced(String, Vec<Arc<TMyNoSqlEntity>>),
+    Deleted(String, Vec<Arc<TMyNoSqlEntity>>),
+    // Subscriber did not keep up and this amount of changes was skipped.
+    // TableInitialized with the current table always follows
+    Lagged(usize),
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> Clone for ReaderChange<TMyNoSqlEntity> {
+    fn clone(&self) -> Self {
+        match self {
+            ReaderChange::TableInitialized(table) => ReaderChange::TableInitialized(table.clone()),
+            ReaderChange::PartitionInitialized(partition_key, partition) => {
+                ReaderChange::PartitionInitialized(partition_key.clone(), partition.clone())
+            }
+            ReaderChange::InsertedOrReplaced(partition_key, entities) => {
+                ReaderChange::InsertedOrReplaced(partition_key.clone(), entities.clone())
+            }
+            ReaderChange::Deleted(partition_key, entities) => {
+                ReaderChange::Deleted(partition_key.clone(), entities.clone())
+            }
+            ReaderChange::Lagged(skipped) => ReaderChange::Lagged(*skipped),
+        }
+    }
+}
+
+struct ChangesSubscriber<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    sender: mpsc::Sender<ReaderChange<TMyNoSqlEntity>>,
+    // Amount of changes skipped since the queue of the subscriber got full
+    lagged: Arc<Mutex<usize>>,
+}
+
+// Every subscriber has its own bounded queue. Changes which do not fit are skipped
+// and the subscriber gets Lagged followed by the whole table once it drains the queue
+pub struct MyNoSqlDataReaderChanges<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    subscribers: Vec<ChangesSubscriber<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static>
+    MyNoSqlDataReaderChanges<TMyNoSqlEntity>
+{
+    pub fn new() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(
+        &mut self,
+        entities: Arc<MyNoSqlDataReaderEntities<TMyNoSqlEntity>>,
+        capacity: usize,
+    ) -> MyNoSqlDataReaderChangesStream<TMyNoSqlEntity> {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+
+        if let Some(current_table) = entities.get_table_snapshot() {
+            let _ = sender.try_send(ReaderChange::TableInitialized(current_table));
+        }
+
+        let lagged = Arc::new(Mutex::new(0));
+
+        self.subscribers.push(ChangesSubscriber {
+            sender,
+            lagged: lagged.clone(),
+        });
+
+        MyNoSqlDataReaderChangesStream {
+            receiver,
+            lagged,
+            entities,
+            resync: None,
+        }
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.is_empty()
+    }
+
+    // Has to be called after the table snapshot with the change is published
+    pub fn send(&mut self, change: ReaderChange<TMyNoSqlEntity>) {
+        self.subscribers.retain(|subscriber| {
+            let mut lagged = subscriber.lagged.lock().unwrap();
+
+            if *lagged > 0 {
+                *lagged += 1;
+                return !subscriber.sender.is_closed();
+            }
+
+            match subscriber.sender.try_send(change.clone()) {
+                Ok(_) => true,
+                Err(TrySendError::Full(_)) => {
+                    *lagged = 1;
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            }
+        });
+    }
+}
+
+pub struct MyNoSqlDataReaderChangesStream<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    receiver: mpsc::Receiver<ReaderChange<TMyNoSqlEntity>>,
+    lagged: Arc<Mutex<usize>>,
+    entities: Arc<MyNoSqlDataReaderEntities<TMyNoSqlEntity>>,
+    resync: Option<ReaderChange<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> Stream
+    for MyNoSqlDataReaderChangesStream<TMyNoSqlEntity>
+{
+    type Item = ReaderChange<TMyNoSqlEntity>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(resync) = this.resync.take() {
+            return Poll::Ready(Some(resync));
+        }
+
+        if let Poll::Ready(change) = this.receiver.poll_recv(cx) {
+            return Poll::Ready(change);
+        }
+
+        // Queue is drained. Table is read under the lag lock, so every change
+        // skipped by the writer is already in it
+        let mut lagged = this.lagged.lock().unwrap();
+
+        if *lagged == 0 {
+            return Poll::Pending;
+        }
+
+        let skipped = std::mem::replace(&mut *lagged, 0);
+        let table = this.entities.get_table_snapshot().unwrap_or_default();
+
+        this.resync = Some(ReaderChange::TableInitialized(table));
+        Poll::Ready(Some(ReaderChange::Lagged(skipped)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use tokio_stream::StreamExt;
+
+    use super::{MyNoSqlDataReaderChanges, ReaderChange};
+    use crate::subscribers::{test_row::TestRow, MyNoSqlDataReaderEntities, MyNoSqlTableEntities};
+
+    fn insert_row(
+        entities: &MyNoSqlDataReaderEntities<TestRow>,
+        row_key: &str,
+    ) -> ReaderChange<TestRow> {
+        let row = Arc::new(TestRow::new("PK".to_string(), row_key.to_string(), 0));
+
+        let mut partition = match entities.get_partition_snapshot("PK") {
+            Some(partition) => partition.as_ref().clone(),
+            None => BTreeMap::new(),
+        };
+        partition.insert(row_key.to_string(), row.clone());
+
+        let mut table = MyNoSqlTableEntities::new();
+        table.insert("PK".to_string(), Arc::new(partition));
+        entities.set_table_snapshot(Some(Arc::new(table)));
+
+        ReaderChange::InsertedOrReplaced("PK".to_string(), vec![row])
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber_gets_resync() {
+        let entities = Arc::new(MyNoSqlDataReaderEntities::new());
+        let mut changes = MyNoSqlDataReaderChanges::new();
+
+        let mut stream = changes.subscribe(entities.clone(), 2);
+
+        for row_key in ["RK1", "RK2", "RK3", "RK4"] {
+            changes.send(insert_row(&entities, row_key));
+        }
+
+        for expected in ["RK1", "RK2"] {
+            match stream.next().await.unwrap() {
+                ReaderChange::InsertedOrReplaced(_, entities) => {
+                    assert_eq!(expected, entities[0].row_key)
+                }
+                _ => panic!("InsertedOrReplaced is expected"),
+            }
+        }
+
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            ReaderChange::Lagged(2)
+        ));
+
+        match stream.next().await.unwrap() {
+            ReaderChange::TableInitialized(table) => assert_eq!(4, table.get("PK").unwrap().len()),
+            _ => panic!("TableInitialized is expected"),
+        }
+
+        changes.send(insert_row(&entities, "RK5"));
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            ReaderChange::InsertedOrReplaced(_, _)
+        ));
+    }
+}
@@ -1,18 +1,32 @@
 use std::{
     collections::{BTreeMap, HashMap},
-    sync::Arc,
+    sync::{Arc, Weak},
 };
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
-use rust_extensions::{lazy::LazyVec, ApplicationStates};
-
-use super::{MyNoSqlDataReaderCallBacks, MyNoSqlDataReaderCallBacksPusher};
+use rust_extensions::{lazy::LazyVec, ApplicationStates, Logger};
+use tokio::sync::watch;
+
+use super::{
+    callback_triggers::CallbackTriggersTarget, MyNoSqlDataReaderCallBacksPusher,
+    MyNoSqlDataReaderChanges, MyNoSqlDataReaderChangesStream, MyNoSqlDataReaderEntities,
+    MyNoSqlDataReaderExtendedCallBacks, MyNoSqlDataReaderIndexes, MyNoSqlDataReaderStatus,
+    MyNoSqlDataReaderView, MyNoSqlReadView, MyNoSqlTableEntities, MyNoSqlTableSnapshot,
+    ReaderChange, TableNotFoundPolicy, UnchangedRowsPolicy,
+};
 
 pub struct MyNoSqlDataReaderData<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
     table_name: &'static str,
-    entities: Option<BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>>,
-    callbacks: Option<Arc<MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>>>,
-    app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
+    entities: Arc<MyNoSqlDataReaderEntities<TMyNoSqlEntity>>,
+    indexes: Arc<MyNoSqlDataReaderIndexes<TMyNoSqlEntity>>,
+    callbacks: Arc<MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>>,
+    // View is maintained while the caller holds it
+    views: Vec<Weak<dyn CallbackTriggersTarget<TMyNoSqlEntity> + Send + Sync + 'static>>,
+    changes: MyNoSqlDataReaderChanges<TMyNoSqlEntity>,
+    status: watch::Sender<MyNoSqlDataReaderStatus>,
+    table_not_found_policy: TableNotFoundPolicy,
+    unchanged_rows_policy: UnchangedRowsPolicy,
+    version: u64,
 }
 
 impl<TMyNoSqlEntity> MyNoSqlDataReaderData<TMyNoSqlEntity>
@@ -23,59 +37,276 @@ where
         table_name: &'static str,
         app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
     ) -> Self {
+        let (status, _) = watch::channel(MyNoSqlDataReaderStatus::Pending);
         Self {
             table_name,
-            entities: None,
-            callbacks: None,
-            app_states,
+            entities: Arc::new(MyNoSqlDataReaderEntities::new()),
+            indexes: Arc::new(MyNoSqlDataReaderIndexes::new()),
+            callbacks: Arc::new(MyNoSqlDataReaderCallBacksPusher::new(
+                table_name, app_states,
+            )),
+            views: Vec::new(),
+            changes: MyNoSqlDataReaderChanges::new(),
+            status,
+            table_not_found_policy: TableNotFoundPolicy::default(),
+            unchanged_rows_policy: UnchangedRowsPolicy::default(),
+            version: 0,
         }
     }
 
-    pub async fn assign_callback<
-        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
+    pub fn assign_callback<
+        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
     >(
         &mut self,
         callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
+        replay_current_table: bool,
     ) {
-        let pusher =
-            MyNoSqlDataReaderCallBacksPusher::new(callbacks, self.app_states.clone()).await;
+        let replay_view = if replay_current_table {
+            Some(self.get_read_view())
+        } else {
+            None
+        };
+
+        self.callbacks.add_callbacks(callbacks, replay_view);
+    }
+
+    // 0 means callbacks receive all the changes of one incoming update at once
+    pub fn set_callbacks_max_batch_size(&mut self, value: usize) {
+        self.callbacks.set_max_batch_size(value);
+    }
+
+    pub fn get_callbacks(&self) -> Arc<MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>> {
+        self.callbacks.clone()
+    }
+
+    pub fn get_entities(&self) -> Arc<MyNoSqlDataReaderEntities<TMyNoSqlEntity>> {
+        self.entities.clone()
+    }
+
+    pub fn get_indexes(&self) -> Arc<MyNoSqlDataReaderIndexes<TMyNoSqlEntity>> {
+        self.indexes.clone()
+    }
 
-        self.callbacks = Some(Arc::new(pusher));
+    pub fn add_index(
+        &mut self,
+        name: String,
+        get_key: Box<dyn Fn(&TMyNoSqlEntity) -> String + Send + Sync + 'static>,
+    ) {
+        let table = self.entities.get_table_snapshot();
+        self.indexes
+            .add_index(name, get_key, table.as_ref().map(|table| table.as_ref()));
     }
-    fn get_init_table(&mut self) -> &mut BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        if self.entities.is_none() {
-            println!("Initialized data for table {}", self.table_name);
-            self.entities = Some(BTreeMap::new());
-            return self.entities.as_mut().unwrap();
+
+    pub fn create_view<TView: Send + Sync + 'static>(
+        &mut self,
+        map: Box<dyn Fn(&TMyNoSqlEntity) -> Option<TView> + Send + Sync + 'static>,
+    ) -> Arc<MyNoSqlDataReaderView<TMyNoSqlEntity, TView>> {
+        let view = Arc::new(MyNoSqlDataReaderView::new(map));
+
+        if let Some(table) = self.entities.get_table_snapshot() {
+            view.init(table.as_ref());
         }
 
-        return self.entities.as_mut().unwrap();
+        let view_callbacks: Arc<
+            dyn CallbackTriggersTarget<TMyNoSqlEntity> + Send + Sync + 'static,
+        > = view.clone();
+        self.views.push(Arc::downgrade(&view_callbacks));
+        view
+    }
+
+    // Views dropped by their holders are removed here
+    fn get_views(
+        &mut self,
+    ) -> Vec<Arc<dyn CallbackTriggersTarget<TMyNoSqlEntity> + Send + Sync + 'static>> {
+        let mut result = Vec::with_capacity(self.views.len());
+
+        self.views.retain(|view| match view.upgrade() {
+            Some(view) => {
+                result.push(view);
+                true
+            }
+            None => false,
+        });
+
+        result
+    }
+
+    pub fn subscribe_changes(
+        &mut self,
+        capacity: usize,
+    ) -> MyNoSqlDataReaderChangesStream<TMyNoSqlEntity> {
+        self.changes.subscribe(self.entities.clone(), capacity)
+    }
+
+    fn set_table_snapshot(
+        &mut self,
+        table: Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>>,
+    ) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.version += 1;
+        self.entities.set_table_snapshot(table)
+    }
+
+    pub fn get_read_view(&self) -> MyNoSqlReadView<TMyNoSqlEntity> {
+        MyNoSqlReadView::new(self.version, self.entities.get_table_snapshot())
+    }
+
+    fn has_change_listeners(&self) -> bool {
+        self.callbacks.has_callbacks()
+            || self.views.iter().any(|view| view.strong_count() > 0)
+            || self.changes.has_subscribers()
+    }
+
+    pub fn get_status(&self) -> MyNoSqlDataReaderStatus {
+        *self.status.borrow()
+    }
+
+    pub fn subscribe_to_status(&self) -> watch::Receiver<MyNoSqlDataReaderStatus> {
+        self.status.subscribe()
+    }
+
+    fn set_status(&self, status: MyNoSqlDataReaderStatus) {
+        self.status.send_if_modified(|current| {
+            if *current == status {
+                return false;
+            }
+
+            *current = status;
+            true
+        });
+    }
+
+    pub fn subscribed(&mut self) {
+        self.set_status(MyNoSqlDataReaderStatus::Subscribed);
+    }
+
+    pub fn disconnected(&mut self) {
+        self.set_status(MyNoSqlDataReaderStatus::Disconnected);
+    }
+
+    pub async fn unsubscribed(&mut self) {
+        let mut indexes_update = self.indexes.start_update();
+        if let Some(indexes_update) = indexes_update.as_mut() {
+            indexes_update.rebuild(&MyNoSqlTableEntities::new());
+        }
+
+        let before = self.set_table_snapshot(None);
+        self.indexes.publish(indexes_update);
+
+        if self.changes.has_subscribers() {
+            self.changes.send(ReaderChange::TableInitialized(Arc::new(
+                MyNoSqlTableEntities::new(),
+            )));
+        }
+
+        for view in self.get_views() {
+            super::callback_triggers::trigger_table_difference(
+                view.as_ref(),
+                before.clone(),
+                &MyNoSqlTableEntities::new(),
+                self.unchanged_rows_policy,
+            )
+            .await;
+        }
+
+        if self.callbacks.has_callbacks() {
+            super::callback_triggers::trigger_table_difference(
+                self.callbacks.as_ref(),
+                before,
+                &MyNoSqlTableEntities::new(),
+                self.unchanged_rows_policy,
+            )
+            .await;
+
+            self.callbacks.flush(self.get_read_view());
+        }
+
+        self.set_status(MyNoSqlDataReaderStatus::Pending);
+    }
+
+    pub fn get_table_not_found_policy(&self) -> TableNotFoundPolicy {
+        self.table_not_found_policy
+    }
+
+    pub fn set_table_not_found_policy(&mut self, policy: TableNotFoundPolicy) {
+        self.table_not_found_policy = policy;
+    }
+
+    pub fn set_unchanged_rows_policy(&mut self, policy: UnchangedRowsPolicy) {
+        self.unchanged_rows_policy = policy;
+    }
+
+    pub fn table_not_found(&mut self) {
+        my_logger::LOGGER.write_error(
+            "MyNoSqlDataReader".to_string(),
+            format!("Table {} is not found on the server", self.table_name),
+            None,
+        );
+        self.set_status(MyNoSqlDataReaderStatus::TableNotFound);
+    }
+
+    // Rows which come before InitTable are applied, but only init_table marks the table as Initialized
+    // Cloning the persistent map is cheap: only the partitions changed later are copied
+    fn get_table_to_update(&self) -> MyNoSqlTableEntities<TMyNoSqlEntity> {
+        match self.entities.get_table_snapshot() {
+            Some(table) => table.as_ref().clone(),
+            None => {
+                println!("Initialized data for table {}", self.table_name);
+                MyNoSqlTableEntities::new()
+            }
+        }
     }
 
     pub async fn init_table(&mut self, data: HashMap<String, Vec<TMyNoSqlEntity>>) {
-        let mut new_table: BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>> =
-            BTreeMap::new();
+        let mut new_table = MyNoSqlTableEntities::new();
 
         for (partition_key, src_entities_by_partition) in data {
-            new_table.insert(partition_key.to_string(), BTreeMap::new());
-
-            let by_partition = new_table.get_mut(partition_key.as_str()).unwrap();
+            let mut by_partition = BTreeMap::new();
 
             for entity in src_entities_by_partition {
                 let entity = Arc::new(entity);
                 by_partition.insert(entity.get_row_key().to_string(), entity);
             }
+
+            new_table.insert(partition_key, Arc::new(by_partition));
         }
 
-        let before = self.entities.replace(new_table);
+        let mut indexes_update = self.indexes.start_update();
+        if let Some(indexes_update) = indexes_update.as_mut() {
+            indexes_update.rebuild(&new_table);
+        }
+
+        let new_table = Arc::new(new_table);
+
+        let before = self.set_table_snapshot(Some(new_table.clone()));
+        self.indexes.publish(indexes_update);
 
-        if let Some(callbacks) = self.callbacks.as_ref() {
+        self.set_status(MyNoSqlDataReaderStatus::Initialized);
+
+        if self.changes.has_subscribers() {
+            self.changes
+                .send(ReaderChange::TableInitialized(new_table.clone()));
+        }
+
+        for view in self.get_views() {
+            super::callback_triggers::trigger_table_difference(
+                view.as_ref(),
+                before.clone(),
+                new_table.as_ref(),
+                self.unchanged_rows_policy,
+            )
+            .await;
+        }
+
+        if self.callbacks.has_callbacks() {
             super::callback_triggers::trigger_table_difference(
-                callbacks.as_ref(),
+                self.callbacks.as_ref(),
                 before,
-                self.entities.as_ref().unwrap(),
+                new_table.as_ref(),
+                self.unchanged_rows_policy,
             )
             .await;
+
+            self.callbacks.flush(self.get_read_view());
         }
     }
 
@@ -84,164 +315,282 @@ where
         partition_key: &str,
         src_entities: HashMap<String, Vec<TMyNoSqlEntity>>,
     ) {
-        let callbacks = self.callbacks.clone();
-
-        let entities = self.get_init_table();
+        let mut entities = self.get_table_to_update();
 
         let mut new_partition = BTreeMap::new();
 
         let before_partition = entities.remove(partition_key);
 
-        for (row_key, entities) in src_entities {
+        for (_, entities) in src_entities {
             for entity in entities {
-                new_partition.insert(row_key.clone(), Arc::new(entity));
+                let entity = Arc::new(entity);
+                new_partition.insert(entity.get_row_key().to_string(), entity);
             }
         }
 
-        entities.insert(partition_key.to_string(), new_partition);
+        let new_partition = Arc::new(new_partition);
+
+        let mut indexes_update = self.indexes.start_update();
+
+        if let Some(indexes_update) = indexes_update.as_mut() {
+            if let Some(before_partition) = before_partition.as_ref() {
+                indexes_update.remove_entities(before_partition.values());
+            }
 
-        if let Some(callbacks) = callbacks {
+            indexes_update.insert_entities(new_partition.values());
+        }
+
+        entities.insert(partition_key.to_string(), new_partition.clone());
+
+        self.set_table_snapshot(Some(Arc::new(entities)));
+        self.indexes.publish(indexes_update);
+
+        if self.changes.has_subscribers() {
+            self.changes.send(ReaderChange::PartitionInitialized(
+                partition_key.to_string(),
+                new_partition.clone(),
+            ));
+        }
+
+        for view in self.get_views() {
             super::callback_triggers::trigger_partition_difference(
-                callbacks.as_ref(),
+                view.as_ref(),
+                partition_key,
+                before_partition.clone(),
+                new_partition.as_ref(),
+                self.unchanged_rows_policy,
+            )
+            .await;
+        }
+
+        if self.callbacks.has_callbacks() {
+            super::callback_triggers::trigger_partition_difference(
+                self.callbacks.as_ref(),
                 partition_key,
                 before_partition,
-                entities.get(partition_key).unwrap(),
+                new_partition.as_ref(),
+                self.unchanged_rows_policy,
             )
             .await;
+
+            self.callbacks.flush(self.get_read_view());
         }
     }
 
-    pub fn update_rows(&mut self, src_data: HashMap<String, Vec<TMyNoSqlEntity>>) {
-        let callbacks = self.callbacks.clone();
+    pub async fn update_rows(&mut self, src_data: HashMap<String, Vec<TMyNoSqlEntity>>) {
+        let mut entities = self.get_table_to_update();
 
-        let entities = self.get_init_table();
+        let mut updates = if self.has_change_listeners() {
+            Some(Vec::new())
+        } else {
+            None
+        };
+
+        let mut index_updates = if self.indexes.has_indexes() {
+            Some(Vec::new())
+        } else {
+            None
+        };
 
         for (partition_key, src_entities) in src_data {
-            let mut updates = if callbacks.is_some() {
-                Some(LazyVec::new())
-            } else {
-                None
+            let mut by_partition = match entities.remove(partition_key.as_str()) {
+                Some(partition) => partition.as_ref().clone(),
+                None => BTreeMap::new(),
             };
 
-            if !entities.contains_key(partition_key.as_str()) {
-                entities.insert(partition_key.to_string(), BTreeMap::new());
-            }
-
-            let by_partition = entities.get_mut(partition_key.as_str()).unwrap();
+            let mut inserted_entities = LazyVec::new();
+            let mut updated_entities = LazyVec::new();
 
             for entity in src_entities {
                 let entity = Arc::new(entity);
-                if let Some(updates) = updates.as_mut() {
-                    updates.add(entity.clone());
+
+                if let Some(index_updates) = index_updates.as_mut() {
+                    index_updates.push(entity.clone());
                 }
-                by_partition.insert(entity.get_row_key().to_string(), entity);
+
+                let before = by_partition.insert(entity.get_row_key().to_string(), entity.clone());
+
+                if updates.is_some() {
+                    match before {
+                        Some(before) => updated_entities.add((before, entity)),
+                        None => inserted_entities.add(entity),
+                    }
+                }
+            }
+
+            entities.insert(partition_key.to_string(), Arc::new(by_partition));
+
+            if let Some(updates) = updates.as_mut() {
+                updates.push((
+                    partition_key,
+                    inserted_entities.get_result(),
+                    updated_entities.get_result(),
+                ));
             }
+        }
+
+        let mut indexes_update = self.indexes.start_update();
+
+        if let (Some(indexes_update), Some(inserted)) = (indexes_update.as_mut(), index_updates) {
+            indexes_update.insert_entities(inserted.iter());
+        }
+
+        self.set_table_snapshot(Some(Arc::new(entities)));
+        self.indexes.publish(indexes_update);
+
+        let views = self.get_views();
+
+        if let Some(updates) = updates {
+            for (partition_key, inserted_entities, updated_entities) in updates {
+                for view in views.iter() {
+                    if let Some(inserted_entities) = inserted_entities.as_ref() {
+                        view.inserted(partition_key.as_str(), inserted_entities.clone())
+                            .await;
+                    }
+
+                    if let Some(updated_entities) = updated_entities.as_ref() {
+                        view.updated(partition_key.as_str(), updated_entities.clone())
+                            .await;
+                    }
+                }
+
+                if self.changes.has_subscribers() {
+                    let mut entities = Vec::new();
+
+                    if let Some(inserted_entities) = inserted_entities.as_ref() {
+                        entities.extend(inserted_entities.iter().cloned());
+                    }
+
+                    if let Some(updated_entities) = updated_entities.as_ref() {
+                        entities.extend(updated_entities.iter().map(|(_, new)| new.clone()));
+                    }
+
+                    self.changes.send(ReaderChange::InsertedOrReplaced(
+                        partition_key.clone(),
+                        entities,
+                    ));
+                }
+
+                if self.callbacks.has_callbacks() {
+                    if let Some(inserted_entities) = inserted_entities {
+                        self.callbacks
+                            .inserted(partition_key.as_str(), inserted_entities);
+                    }
 
-            if let Some(callbacks) = callbacks.as_ref() {
-                if let Some(updates) = updates {
-                    if let Some(updates) = updates.get_result() {
-                        callbacks.inserted_or_replaced(partition_key.as_str(), updates);
+                    if let Some(updated_entities) = updated_entities {
+                        self.callbacks
+                            .updated(partition_key.as_str(), updated_entities);
                     }
                 }
             }
         }
-    }
 
-    pub fn delete_rows(&mut self, rows_to_delete: Vec<my_no_sql_tcp_shared::DeleteRowTcpContract>) {
-        let callbacks = self.callbacks.clone();
+        self.callbacks.flush(self.get_read_view());
+    }
 
-        let mut deleted_rows = if callbacks.is_some() {
+    pub async fn delete_rows(
+        &mut self,
+        rows_to_delete: Vec<my_no_sql_tcp_shared::DeleteRowTcpContract>,
+    ) {
+        let mut deleted_rows = if self.has_change_listeners() {
             Some(HashMap::new())
         } else {
             None
         };
 
-        let entities = self.get_init_table();
+        let mut removed_from_indexes = if self.indexes.has_indexes() {
+            Some(Vec::new())
+        } else {
+            None
+        };
 
-        for row_to_delete in &rows_to_delete {
-            let mut delete_partition = false;
-            if let Some(partition) = entities.get_mut(row_to_delete.partition_key.as_str()) {
-                if partition.remove(row_to_delete.row_key.as_str()).is_some() {
-                    if let Some(deleted_rows) = deleted_rows.as_mut() {
-                        if !deleted_rows.contains_key(row_to_delete.partition_key.as_str()) {
-                            deleted_rows
-                                .insert(row_to_delete.partition_key.to_string(), Vec::new());
-                        }
+        let mut entities = self.get_table_to_update();
+
+        let mut rows_by_partition: HashMap<String, Vec<String>> = HashMap::new();
+
+        for row_to_delete in rows_to_delete {
+            rows_by_partition
+                .entry(row_to_delete.partition_key)
+                .or_insert_with(Vec::new)
+                .push(row_to_delete.row_key);
+        }
 
+        for (partition_key, row_keys) in rows_by_partition {
+            let mut partition = match entities.remove(partition_key.as_str()) {
+                Some(partition) => partition.as_ref().clone(),
+                None => continue,
+            };
+
+            for row_key in row_keys {
+                if let Some(removed) = partition.remove(row_key.as_str()) {
+                    if let Some(removed_from_indexes) = removed_from_indexes.as_mut() {
+                        removed_from_indexes.push(removed.clone());
+                    }
+
+                    if let Some(deleted_rows) = deleted_rows.as_mut() {
                         deleted_rows
-                            .get_mut(row_to_delete.partition_key.as_str())
-                            .unwrap()
-                            .push(
-                                partition
-                                    .get(row_to_delete.row_key.as_str())
-                                    .unwrap()
-                                    .clone(),
-                            );
+                            .entry(partition_key.to_string())
+                            .or_insert_with(Vec::new)
+                            .push(removed);
                     }
                 }
-
-                delete_partition = partition.len() == 0;
             }
 
-            if delete_partition {
-                entities.remove(row_to_delete.partition_key.as_str());
+            if !partition.is_empty() {
+                entities.insert(partition_key, Arc::new(partition));
             }
         }
 
-        if let Some(callbacks) = callbacks.as_ref() {
-            if let Some(partitions) = deleted_rows {
-                for (partition_key, rows) in partitions {
-                    callbacks.deleted(partition_key.as_str(), rows);
-                }
-            }
-        }
-    }
+        let mut indexes_update = self.indexes.start_update();
 
-    pub fn get_table_snapshot(
-        &self,
-    ) -> Option<BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>> {
-        let entities = self.entities.as_ref()?;
+        if let (Some(indexes_update), Some(removed)) =
+            (indexes_update.as_mut(), removed_from_indexes)
+        {
+            indexes_update.remove_entities(removed.iter());
+        }
 
-        return Some(entities.clone());
-    }
+        self.set_table_snapshot(Some(Arc::new(entities)));
+        self.indexes.publish(indexes_update);
 
-    pub fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let entities = self.entities.as_ref()?;
+        let views = self.get_views();
 
-        if entities.len() == 0 {
-            return None;
-        }
+        if let Some(partitions) = deleted_rows {
+            for (partition_key, rows) in partitions {
+                for view in views.iter() {
+                    view.deleted(partition_key.as_str(), rows.clone()).await;
+                }
 
-        let mut result = Vec::new();
+                if self.changes.has_subscribers() {
+                    self.changes
+                        .send(ReaderChange::Deleted(partition_key.clone(), rows.clone()));
+                }
 
-        for partition in entities.values() {
-            for entity in partition.values() {
-                result.push(entity.clone());
+                if self.callbacks.has_callbacks() {
+                    self.callbacks.deleted(partition_key.as_str(), rows);
+                }
             }
         }
 
-        Some(result)
+        self.callbacks.flush(self.get_read_view());
     }
 
-    pub fn get_entity(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
-        let entities = self.entities.as_ref()?;
-
-        let partition = entities.get(partition_key)?;
+    pub fn get_table_snapshot(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.entities.get_table_snapshot()
+    }
 
-        let row = partition.get(row_key)?;
+    pub fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.entities.get_table_snapshot_as_vec()
+    }
 
-        Some(row.clone())
+    pub fn get_entity(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
+        self.entities.get_entity(partition_key, row_key)
     }
 
     pub fn get_by_partition(
         &self,
         partition_key: &str,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let entities = self.entities.as_ref()?;
-
-        let partition = entities.get(partition_key)?;
-
-        Some(partition.clone())
+        self.entities.get_by_partition(partition_key)
     }
 
     pub fn get_by_partition_with_filter(
@@ -249,76 +598,98 @@ where
         partition_key: &str,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let entities = self.entities.as_ref()?;
-
-        let partition = entities.get(partition_key)?;
+        self.entities
+            .get_by_partition_with_filter(partition_key, filter)
+    }
 
-        let mut result = BTreeMap::new();
+    pub fn has_partition(&self, partition_key: &str) -> bool {
+        self.entities.has_partition(partition_key)
+    }
 
-        for db_row in partition.values() {
-            if filter(db_row) {
-                result.insert(db_row.get_row_key().to_string(), db_row.clone());
-            }
-        }
+    pub fn get_by_partition_as_vec(&self, partition_key: &str) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.entities.get_by_partition_as_vec(partition_key)
+    }
 
-        Some(result)
+    pub fn get_by_partition_as_vec_with_filter(
+        &self,
+        partition_key: &str,
+        filter: impl Fn(&TMyNoSqlEntity) -> bool,
+    ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.entities
+            .get_by_partition_as_vec_with_filter(partition_key, filter)
     }
 
-    pub fn has_partition(&self, partition_key: &str) -> bool {
-        let entities = self.entities.as_ref();
+    pub async fn has_entities_at_all(&self) -> bool {
+        self.entities.has_entities_at_all()
+    }
+}
 
-        if entities.is_none() {
-            return false;
-        }
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
 
-        let entities = entities.unwrap();
+    use super::MyNoSqlDataReaderData;
+    use crate::subscribers::{
+        test_row::{TestAppStates, TestRow},
+        MyNoSqlDataReaderStatus,
+    };
 
-        entities.contains_key(partition_key)
+    async fn create_data() -> MyNoSqlDataReaderData<TestRow> {
+        MyNoSqlDataReaderData::new("Test", Arc::new(TestAppStates)).await
     }
 
-    pub fn get_by_partition_as_vec(&self, partition_key: &str) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let entities = self.entities.as_ref()?;
-
-        let partition = entities.get(partition_key)?;
+    fn create_rows(partition_key: &str, row_keys: &[&str]) -> HashMap<String, Vec<TestRow>> {
+        let rows = row_keys
+            .iter()
+            .map(|row_key| TestRow::new(partition_key.to_string(), row_key.to_string(), 0))
+            .collect();
 
-        if partition.len() == 0 {
-            return None;
-        }
+        let mut result = HashMap::new();
+        result.insert(partition_key.to_string(), rows);
+        result
+    }
 
-        let mut result = Vec::with_capacity(partition.len());
+    #[tokio::test]
+    async fn test_only_init_table_initializes_the_reader() {
+        let mut data = create_data().await;
 
-        for db_row in partition.values() {
-            result.push(db_row.clone());
-        }
+        data.update_rows(create_rows("PK1", &["RK1"])).await;
+        data.init_partition("PK2", create_rows("PK2", &["RK1"]))
+            .await;
+        assert_eq!(MyNoSqlDataReaderStatus::Pending, data.get_status());
 
-        Some(result)
+        data.init_table(create_rows("PK1", &["RK1", "RK2"])).await;
+        assert_eq!(MyNoSqlDataReaderStatus::Initialized, data.get_status());
     }
 
-    pub fn get_by_partition_as_vec_with_filter(
-        &self,
-        partition_key: &str,
-        filter: impl Fn(&TMyNoSqlEntity) -> bool,
-    ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let entities = self.entities.as_ref()?;
+    #[tokio::test]
+    async fn test_update_shares_untouched_partitions() {
+        let mut data = create_data().await;
 
-        let partition = entities.get(partition_key)?;
+        let mut table = create_rows("PK1", &["RK1"]);
+        table.extend(create_rows("PK2", &["RK1"]));
+        table.extend(create_rows("PK3", &["RK1"]));
+        data.init_table(table).await;
 
-        if partition.len() == 0 {
-            return None;
-        }
+        let before = data.get_table_snapshot().unwrap();
 
-        let mut result = Vec::with_capacity(partition.len());
+        data.update_rows(create_rows("PK2", &["RK2"])).await;
 
-        for db_row in partition.values() {
-            if filter(db_row.as_ref()) {
-                result.push(db_row.clone());
-            }
-        }
-
-        Some(result)
-    }
+        data.init_partition("PK3", create_rows("PK3", &["RK2"]))
+            .await;
 
-    pub async fn has_entities_at_all(&self) -> bool {
-        self.entities.is_some()
+        let after = data.get_table_snapshot().unwrap();
+
+        assert!(Arc::ptr_eq(
+            before.get("PK1").unwrap(),
+            after.get("PK1").unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            before.get("PK2").unwrap(),
+            after.get("PK2").unwrap()
+        ));
+        assert_eq!(2, after.get("PK2").unwrap().len());
+        assert!(after.get("PK3").unwrap().contains_key("RK2"));
+        assert!(before.get("PK3").unwrap().contains_key("RK1"));
     }
 }
@@ -0,0 +1,257 @@
+use std::{collections::BTreeMap, ops::Bound, sync::Arc};
+
+use arc_swap::ArcSwapOption;
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+pub type MyNoSqlPartitionSnapshot<TMyNoSqlEntity> = Arc<BTreeMap<String, Arc<TMyNoSqlEntity>>>;
+// Persistent map: an update copies only the partitions it touches,
+// the rest of them stay shared with the previous snapshot
+pub type MyNoSqlTableEntities<TMyNoSqlEntity> =
+    im::OrdMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>>;
+pub type MyNoSqlTableSnapshot<TMyNoSqlEntity> = Arc<MyNoSqlTableEntities<TMyNoSqlEntity>>;
+
+// Immutable table snapshot which is swapped as a whole by the writer. Readers never wait for the writer.
+pub struct MyNoSqlDataReaderEntities<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    table: ArcSwapOption<MyNoSqlTableEntities<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity> MyNoSqlDataReaderEntities<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            table: ArcSwapOption::empty(),
+        }
+    }
+
+    pub fn get_table_snapshot(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.table.load_full()
+    }
+
+    pub fn set_table_snapshot(
+        &self,
+        table: Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>>,
+    ) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.table.swap(table)
+    }
+
+    pub fn has_entities_at_all(&self) -> bool {
+        self.table.load().is_some()
+    }
+
+    pub fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let table = self.table.load();
+        let entities = Option::as_ref(&table)?;
+
+        if entities.is_empty() {
+            return None;
+        }
+
+        let mut result = Vec::new();
+
+        for partition in entities.values() {
+            for entity in partition.values() {
+                result.push(entity.clone());
+            }
+        }
+
+        Some(result)
+    }
+
+    pub fn get_entity(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
+        let table = self.table.load();
+        let entities = Option::as_ref(&table)?;
+
+        let partition = entities.get(partition_key)?;
+
+        let row = partition.get(row_key)?;
+
+        Some(row.clone())
+    }
+
+    pub fn get_entities_by_keys(&self, keys: &[(&str, &str)]) -> Vec<Arc<TMyNoSqlEntity>> {
+        let table = self.table.load();
+
+        let entities = match Option::as_ref(&table) {
+            Some(entities) => entities,
+            None => return vec![],
+        };
+
+        let mut result = Vec::with_capacity(keys.len());
+
+        for (partition_key, row_key) in keys {
+            if let Some(partition) = entities.get(*partition_key) {
+                if let Some(entity) = partition.get(*row_key) {
+                    result.push(entity.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn get_partitions(
+        &self,
+        partition_keys: &[&str],
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        let mut result = BTreeMap::new();
+
+        let table = self.table.load();
+
+        if let Some(entities) = Option::as_ref(&table) {
+            for partition_key in partition_keys {
+                if let Some(partition) = entities.get(*partition_key) {
+                    result.insert(partition_key.to_string(), partition.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn get_partition_snapshot(
+        &self,
+        partition_key: &str,
+    ) -> Option<MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        let table = self.table.load();
+        let entities = Option::as_ref(&table)?;
+
+        let partition = entities.get(partition_key)?;
+
+        Some(partition.clone())
+    }
+
+    pub fn get_partitions_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        let mut result = BTreeMap::new();
+
+        let table = self.table.load();
+        let entities = match Option::as_ref(&table) {
+            Some(entities) => entities,
+            None => return result,
+        };
+
+        let range = entities.range::<_, str>((Bound::Included(prefix), Bound::Unbounded));
+
+        for (partition_key, partition) in range {
+            if !partition_key.starts_with(prefix) {
+                break;
+            }
+
+            result.insert(partition_key.to_string(), partition.clone());
+        }
+
+        result
+    }
+
+    pub fn get_by_partition(
+        &self,
+        partition_key: &str,
+    ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
+        let partition = self.get_partition_snapshot(partition_key)?;
+
+        Some(partition.as_ref().clone())
+    }
+
+    pub fn get_by_partition_with_filter(
+        &self,
+        partition_key: &str,
+        filter: impl Fn(&TMyNoSqlEntity) -> bool,
+    ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
+        let partition = self.get_partition_snapshot(partition_key)?;
+
+        let mut result = BTreeMap::new();
+
+        for db_row in partition.values() {
+            if filter(db_row) {
+                result.insert(db_row.get_row_key().to_string(), db_row.clone());
+            }
+        }
+
+        Some(result)
+    }
+
+    pub fn has_partition(&self, partition_key: &str) -> bool {
+        let table = self.table.load();
+
+        match Option::as_ref(&table) {
+            Some(entities) => entities.contains_key(partition_key),
+            None => false,
+        }
+    }
+
+    pub fn get_by_partition_as_vec(&self, partition_key: &str) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let partition = self.get_partition_snapshot(partition_key)?;
+
+        if partition.is_empty() {
+            return None;
+        }
+
+        let mut result = Vec::with_capacity(partition.len());
+
+        for db_row in partition.values() {
+            result.push(db_row.clone());
+        }
+
+        Some(result)
+    }
+
+    pub fn get_by_partition_as_vec_with_filter(
+        &self,
+        partition_key: &str,
+        filter: impl Fn(&TMyNoSqlEntity) -> bool,
+    ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let partition = self.get_partition_snapshot(partition_key)?;
+
+        if partition.is_empty() {
+            return None;
+        }
+
+        let mut result = Vec::with_capacity(partition.len());
+
+        for db_row in partition.values() {
+            if filter(db_row.as_ref()) {
+                result.push(db_row.clone());
+            }
+        }
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use super::{MyNoSqlDataReaderEntities, MyNoSqlTableEntities};
+    use crate::subscribers::test_row::TestRow;
+
+    #[test]
+    fn test_get_partitions_skips_missing_keys() {
+        let entities: MyNoSqlDataReaderEntities<TestRow> = MyNoSqlDataReaderEntities::new();
+        assert!(entities.get_partitions(&["PK1"]).is_empty());
+
+        let mut table = MyNoSqlTableEntities::new();
+
+        for partition_key in ["PK1", "PK2"] {
+            let row = Arc::new(TestRow::new(
+                partition_key.to_string(),
+                "RK1".to_string(),
+                0,
+            ));
+            let mut partition = BTreeMap::new();
+            partition.insert("RK1".to_string(), row);
+            table.insert(partition_key.to_string(), Arc::new(partition));
+        }
+
+        entities.set_table_snapshot(Some(Arc::new(table)));
+
+        let result = entities.get_partitions(&["PK2", "PK3", "PK2"]);
+
+        assert_eq!(1, result.len());
+        assert!(result.contains_key("PK2"));
+    }
+}
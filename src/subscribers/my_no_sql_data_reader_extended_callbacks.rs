@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+use super::{MyNoSqlDataReaderCallBacks, MyNoSqlReadView};
+
+pub type CallbackResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+// Each call gets the read view of the table matching the delivered event, even if the reader
+// has already applied newer updates. Failed and panicked calls are retried according to
+// CallbacksRetryPolicy and then handed to the dead letter handler
+#[async_trait::async_trait]
+pub trait MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static>
+{
+    async fn inserted(
+        &self,
+        view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        partition_key: &str,
+        entities: Vec<Arc<TMyNoSqlEntity>>,
+    ) -> CallbackResult;
+
+    // Pairs of (old, new) versions of the same row. New versions are delivered to inserted by default
+    async fn updated(
+        &self,
+        view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        partition_key: &str,
+        entities: Vec<(Arc<TMyNoSqlEntity>, Arc<TMyNoSqlEntity>)>,
+    ) -> CallbackResult {
+        let entities = entities.into_iter().map(|(_, new)| new).collect();
+        self.inserted(view, partition_key, entities).await
+    }
+
+    async fn deleted(
+        &self,
+        view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        partition_key: &str,
+        entities: Vec<Arc<TMyNoSqlEntity>>,
+    ) -> CallbackResult;
+
+    // Delivered instead of the coalesced events once the callbacks queue overflows.
+    // Carries the whole current content of the partition. Rows deleted in between are delivered
+    // as deleted right before it; partition without rows gets no resync_partition.
+    async fn resync_partition(
+        &self,
+        view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        partition_key: &str,
+        entities: Vec<Arc<TMyNoSqlEntity>>,
+    ) -> CallbackResult {
+        self.inserted(view, partition_key, entities).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<TMyNoSqlEntity, TMyNoSqlDataReaderCallBacks> MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity>
+    for TMyNoSqlDataReaderCallBacks
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+    TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync,
+{
+    async fn inserted(
+        &self,
+        _view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        partition_key: &str,
+        entities: Vec<Arc<TMyNoSqlEntity>>,
+    ) -> CallbackResult {
+        self.inserted_or_replaced(partition_key, entities).await;
+        Ok(())
+    }
+
+    async fn deleted(
+        &self,
+        _view: &MyNoSqlReadView<TMyNoSqlEntity>,
+        partition_key: &str,
+        entities: Vec<Arc<TMyNoSqlEntity>>,
+    ) -> CallbackResult {
+        MyNoSqlDataReaderCallBacks::deleted(self, partition_key, entities).await;
+        Ok(())
+    }
+}
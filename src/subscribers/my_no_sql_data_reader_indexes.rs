@@ -0,0 +1,258 @@
+use std::{collections::HashMap, sync::Arc};
+
+use arc_swap::ArcSwap;
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+use super::MyNoSqlTableEntities;
+
+// Persistent maps: the writer clones the index and changes only the keys of the updated rows
+pub struct SecondaryIndex<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    get_key: Arc<dyn Fn(&TMyNoSqlEntity) -> String + Send + Sync + 'static>,
+    items: im::OrdMap<String, im::OrdMap<(String, String), Arc<TMyNoSqlEntity>>>,
+    keys: im::OrdMap<(String, String), String>,
+}
+
+impl<TMyNoSqlEntity> Clone for SecondaryIndex<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            get_key: self.get_key.clone(),
+            items: self.items.clone(),
+            keys: self.keys.clone(),
+        }
+    }
+}
+
+impl<TMyNoSqlEntity> SecondaryIndex<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    pub fn new(get_key: Box<dyn Fn(&TMyNoSqlEntity) -> String + Send + Sync + 'static>) -> Self {
+        Self {
+            get_key: Arc::from(get_key),
+            items: im::OrdMap::new(),
+            keys: im::OrdMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, entity: &Arc<TMyNoSqlEntity>) {
+        self.remove(entity);
+
+        let key = (self.get_key)(entity);
+        let row_id = (
+            entity.get_partition_key().to_string(),
+            entity.get_row_key().to_string(),
+        );
+
+        self.items
+            .entry(key.clone())
+            .or_default()
+            .insert(row_id.clone(), entity.clone());
+
+        self.keys.insert(row_id, key);
+    }
+
+    pub fn remove(&mut self, entity: &TMyNoSqlEntity) {
+        let row_id = (
+            entity.get_partition_key().to_string(),
+            entity.get_row_key().to_string(),
+        );
+
+        let key = match self.keys.remove(&row_id) {
+            Some(key) => key,
+            None => return,
+        };
+
+        let mut remove_key = false;
+
+        if let Some(by_key) = self.items.get_mut(key.as_str()) {
+            by_key.remove(&row_id);
+            remove_key = by_key.is_empty();
+        }
+
+        if remove_key {
+            self.items.remove(key.as_str());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.keys.clear();
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let by_key = self.items.get(key)?;
+        Some(by_key.values().cloned().collect())
+    }
+}
+
+// Lookups read the published snapshot and never wait for the writer.
+// The writer changes its own copy and publishes it right after the table snapshot
+pub struct MyNoSqlDataReaderIndexes<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    indexes: ArcSwap<HashMap<String, SecondaryIndex<TMyNoSqlEntity>>>,
+}
+
+impl<TMyNoSqlEntity> MyNoSqlDataReaderIndexes<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            indexes: ArcSwap::from_pointee(HashMap::new()),
+        }
+    }
+
+    pub fn has_indexes(&self) -> bool {
+        !self.indexes.load().is_empty()
+    }
+
+    pub fn add_index(
+        &self,
+        name: String,
+        get_key: Box<dyn Fn(&TMyNoSqlEntity) -> String + Send + Sync + 'static>,
+        table: Option<&MyNoSqlTableEntities<TMyNoSqlEntity>>,
+    ) {
+        let mut index = SecondaryIndex::new(get_key);
+
+        if let Some(table) = table {
+            for partition in table.values() {
+                for entity in partition.values() {
+                    index.insert(entity);
+                }
+            }
+        }
+
+        let mut indexes = self.indexes.load().as_ref().clone();
+        indexes.insert(name, index);
+        self.indexes.store(Arc::new(indexes));
+    }
+
+    // None if there are no indexes: the writer skips the index work completely
+    pub fn start_update(&self) -> Option<MyNoSqlDataReaderIndexesUpdate<TMyNoSqlEntity>> {
+        let indexes = self.indexes.load();
+
+        if indexes.is_empty() {
+            return None;
+        }
+
+        Some(MyNoSqlDataReaderIndexesUpdate {
+            indexes: indexes.as_ref().clone(),
+        })
+    }
+
+    // Has to be called after the table snapshot is swapped,
+    // so an index lookup never returns rows of a table version which is not published
+    pub fn publish(&self, update: Option<MyNoSqlDataReaderIndexesUpdate<TMyNoSqlEntity>>) {
+        if let Some(update) = update {
+            self.indexes.store(Arc::new(update.indexes));
+        }
+    }
+
+    pub fn get_by_index(&self, name: &str, key: &str) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.indexes.load().get(name)?.get(key)
+    }
+}
+
+pub struct MyNoSqlDataReaderIndexesUpdate<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    indexes: HashMap<String, SecondaryIndex<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity> MyNoSqlDataReaderIndexesUpdate<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    pub fn rebuild(&mut self, table: &MyNoSqlTableEntities<TMyNoSqlEntity>) {
+        for index in self.indexes.values_mut() {
+            index.clear();
+
+            for partition in table.values() {
+                for entity in partition.values() {
+                    index.insert(entity);
+                }
+            }
+        }
+    }
+
+    pub fn insert_entities<'e>(
+        &mut self,
+        entities: impl Iterator<Item = &'e Arc<TMyNoSqlEntity>> + Clone,
+    ) {
+        for index in self.indexes.values_mut() {
+            for entity in entities.clone() {
+                index.insert(entity);
+            }
+        }
+    }
+
+    pub fn remove_entities<'e>(
+        &mut self,
+        entities: impl Iterator<Item = &'e Arc<TMyNoSqlEntity>> + Clone,
+    ) {
+        for index in self.indexes.values_mut() {
+            for entity in entities.clone() {
+                index.remove(entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{MyNoSqlDataReaderIndexes, SecondaryIndex};
+    use crate::subscribers::test_row::TestRow;
+
+    fn create_row(partition_key: &str, row_key: &str, symbol: &str) -> Arc<TestRow> {
+        Arc::new(TestRow::with_value(partition_key, row_key, symbol))
+    }
+
+    #[test]
+    fn test_index_follows_row_changes() {
+        let mut index: SecondaryIndex<TestRow> =
+            SecondaryIndex::new(Box::new(|itm: &TestRow| itm.value.clone()));
+
+        let row1 = create_row("PK1", "RK1", "EURUSD");
+        let row2 = create_row("PK2", "RK1", "EURUSD");
+
+        index.insert(&row1);
+        index.insert(&row2);
+
+        assert_eq!(2, index.get("EURUSD").unwrap().len());
+
+        let row1_updated = create_row("PK1", "RK1", "BTCUSD");
+        index.insert(&row1_updated);
+
+        assert_eq!(1, index.get("EURUSD").unwrap().len());
+        assert_eq!(1, index.get("BTCUSD").unwrap().len());
+
+        index.remove(&row2);
+        assert!(index.get("EURUSD").is_none());
+    }
+
+    #[test]
+    fn test_update_is_visible_only_after_publish() {
+        let indexes: MyNoSqlDataReaderIndexes<TestRow> = MyNoSqlDataReaderIndexes::new();
+        assert!(indexes.start_update().is_none());
+
+        indexes.add_index(
+            "by_symbol".to_string(),
+            Box::new(|itm: &TestRow| itm.value.clone()),
+            None,
+        );
+
+        let row = create_row("PK1", "RK1", "EURUSD");
+
+        let mut update = indexes.start_update().unwrap();
+        update.insert_entities([row].iter());
+        assert!(indexes.get_by_index("by_symbol", "EURUSD").is_none());
+
+        indexes.publish(Some(update));
+        assert_eq!(
+            1,
+            indexes.get_by_index("by_symbol", "EURUSD").unwrap().len()
+        );
+    }
+}
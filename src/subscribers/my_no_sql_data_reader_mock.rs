@@ -2,7 +2,11 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 
-use super::{GetEntitiesBuilder, GetEntityBuilder, MyNoSqlDataReader, MyNoSqlDataReaderMockInner};
+use super::{
+    GetEntitiesBuilder, GetEntitiesByKeysBuilder, GetEntityBuilder, MyNoSqlDataReader,
+    MyNoSqlDataReaderMockInner, MyNoSqlDataReaderSync, MyNoSqlPartitionSnapshot,
+    MyNoSqlTableSnapshot, QueryBuilder,
+};
 
 pub struct MyNoSqlDataReaderMock<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
     pub inner: Arc<MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
@@ -24,6 +28,10 @@ where
     pub async fn delete(&self, to_delete: impl Iterator<Item = (String, String)>) {
         self.inner.delete(to_delete).await;
     }
+
+    pub fn clear(&self) {
+        self.inner.clear();
+    }
 }
 
 #[async_trait::async_trait]
@@ -32,25 +40,25 @@ where
     TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static,
 {
     async fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        self.inner.get_table_snapshot_as_vec().await
+        self.inner.get_table_snapshot_as_vec()
     }
 
     async fn get_by_partition_key(
         &self,
         partition_key: &str,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        self.inner.get_by_partition_key(partition_key).await
+        self.inner.get_by_partition_key(partition_key)
     }
 
     async fn get_by_partition_key_as_vec(
         &self,
         partition_key: &str,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        self.inner.get_by_partition_key_as_vec(partition_key).await
+        self.inner.get_by_partition_key_as_vec(partition_key)
     }
 
     async fn get_entity(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
-        self.inner.get_entity(partition_key, row_key).await
+        self.inner.get_entity(partition_key, row_key)
     }
 
     fn get_entities<'s>(&self, partition_key: &'s str) -> GetEntitiesBuilder<TMyNoSqlEntity> {
@@ -66,6 +74,63 @@ where
     }
 
     async fn has_partition(&self, partition_key: &str) -> bool {
-        self.inner.has_partition(partition_key).await
+        self.inner.has_partition(partition_key)
+    }
+
+    async fn get_entities_by_keys(&self, keys: &[(&str, &str)]) -> Vec<Arc<TMyNoSqlEntity>> {
+        self.inner.get_entities_by_keys(keys)
+    }
+
+    async fn get_partitions(
+        &self,
+        partition_keys: &[&str],
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        self.inner.get_partitions(partition_keys)
+    }
+
+    fn get_entities_by_keys_with_callback_to_server<'s>(
+        &'s self,
+        keys: &'s [(&'s str, &'s str)],
+    ) -> GetEntitiesByKeysBuilder<'s, TMyNoSqlEntity> {
+        GetEntitiesByKeysBuilder::new_mock(keys, self.inner.clone())
+    }
+
+    fn query(&self) -> QueryBuilder<TMyNoSqlEntity> {
+        QueryBuilder::new_mock(self.inner.clone())
+    }
+}
+
+impl<TMyNoSqlEntity> MyNoSqlDataReaderSync<TMyNoSqlEntity> for MyNoSqlDataReaderMock<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static,
+{
+    fn get_table_snapshot_sync(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.inner.get_table_snapshot()
+    }
+
+    fn get_table_snapshot_as_vec_sync(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.inner.get_table_snapshot_as_vec()
+    }
+
+    fn get_by_partition_key_sync(
+        &self,
+        partition_key: &str,
+    ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
+        self.inner.get_by_partition_key(partition_key)
+    }
+
+    fn get_by_partition_key_as_vec_sync(
+        &self,
+        partition_key: &str,
+    ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.inner.get_by_partition_key_as_vec(partition_key)
+    }
+
+    fn get_entity_sync(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
+        self.inner.get_entity(partition_key, row_key)
+    }
+
+    fn has_partition_sync(&self, partition_key: &str) -> bool {
+        self.inner.has_partition(partition_key)
     }
 }
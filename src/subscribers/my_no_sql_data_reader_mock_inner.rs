@@ -1,14 +1,16 @@
 use std::{
     collections::{BTreeMap, HashSet},
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use rust_extensions::lazy::LazyVec;
-use tokio::sync::RwLock;
+
+use super::{MyNoSqlPartitionSnapshot, MyNoSqlTableEntities, MyNoSqlTableSnapshot};
 
 pub struct MyNoSqlDataReaderMockInner<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
-    pub data: RwLock<BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>>,
+    // std lock: the sync reader methods read it as well. Changed through update, delete and clear
+    pub(crate) data: RwLock<BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>>,
 }
 
 impl<TMyNoSqlEntity> MyNoSqlDataReaderMockInner<TMyNoSqlEntity>
@@ -22,7 +24,7 @@ where
     }
 
     pub async fn update(&self, items: impl Iterator<Item = Arc<TMyNoSqlEntity>>) {
-        let mut write_access = self.data.write().await;
+        let mut write_access = self.data.write().unwrap();
         for item in items {
             let partition_key = item.get_partition_key();
             let row_key = item.get_row_key();
@@ -34,7 +36,7 @@ where
         }
     }
     pub async fn delete(&self, to_delete: impl Iterator<Item = (String, String)>) {
-        let mut write_access = self.data.write().await;
+        let mut write_access = self.data.write().unwrap();
 
         let mut partitions_to_remove = HashSet::new();
         for (partition_key, row_key) in to_delete {
@@ -54,8 +56,12 @@ where
         }
     }
 
-    pub async fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let read_access = self.data.read().await;
+    pub fn clear(&self) {
+        self.data.write().unwrap().clear();
+    }
+
+    pub fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let read_access = self.data.read().unwrap();
         let mut result = LazyVec::new();
         for partition in read_access.values() {
             for item in partition.values() {
@@ -66,19 +72,36 @@ where
         result.get_result()
     }
 
-    pub async fn get_by_partition_key(
+    pub fn get_table_snapshot(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        let read_access = self.data.read().unwrap();
+
+        // Same as the real reader: a table without entities was not initialized yet
+        if read_access.is_empty() {
+            return None;
+        }
+
+        let mut result = MyNoSqlTableEntities::new();
+
+        for (partition_key, partition) in read_access.iter() {
+            result.insert(partition_key.to_string(), Arc::new(partition.clone()));
+        }
+
+        Some(Arc::new(result))
+    }
+
+    pub fn get_by_partition_key(
         &self,
         partition_key: &str,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let read_access = self.data.read().await;
+        let read_access = self.data.read().unwrap();
         read_access.get(partition_key).cloned()
     }
 
-    pub async fn get_by_partition_key_as_vec(
+    pub fn get_by_partition_key_as_vec(
         &self,
         partition_key: &str,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let read_access = self.data.read().await;
+        let read_access = self.data.read().unwrap();
         let mut result = LazyVec::new();
         if let Some(partition) = read_access.get(partition_key) {
             for item in partition.values() {
@@ -89,12 +112,12 @@ where
         result.get_result()
     }
 
-    pub async fn get_by_partition_key_as_vec_with_filter(
+    pub fn get_by_partition_key_as_vec_with_filter(
         &self,
         partition_key: &str,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let read_access = self.data.read().await;
+        let read_access = self.data.read().unwrap();
         let mut result = LazyVec::new();
         if let Some(partition) = read_access.get(partition_key) {
             for item in partition.values() {
@@ -107,20 +130,46 @@ where
         result.get_result()
     }
 
-    pub async fn get_entity(
-        &self,
-        partition_key: &str,
-        row_key: &str,
-    ) -> Option<Arc<TMyNoSqlEntity>> {
-        let read_access = self.data.read().await;
+    pub fn get_entity(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
+        let read_access = self.data.read().unwrap();
         read_access
             .get(partition_key)
             .and_then(|partition| partition.get(row_key))
             .cloned()
     }
 
-    pub async fn get_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let read_access = self.data.read().await;
+    pub fn get_entities_by_keys(&self, keys: &[(&str, &str)]) -> Vec<Arc<TMyNoSqlEntity>> {
+        let read_access = self.data.read().unwrap();
+
+        keys.iter()
+            .filter_map(|(partition_key, row_key)| {
+                read_access
+                    .get(*partition_key)
+                    .and_then(|partition| partition.get(*row_key))
+                    .cloned()
+            })
+            .collect()
+    }
+
+    pub fn get_partitions(
+        &self,
+        partition_keys: &[&str],
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        let read_access = self.data.read().unwrap();
+
+        let mut result = BTreeMap::new();
+
+        for partition_key in partition_keys {
+            if let Some(partition) = read_access.get(*partition_key) {
+                result.insert(partition_key.to_string(), Arc::new(partition.clone()));
+            }
+        }
+
+        result
+    }
+
+    pub fn get_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        let read_access = self.data.read().unwrap();
         let mut result = LazyVec::new();
         for partition in read_access.values() {
             for item in partition.values() {
@@ -131,11 +180,11 @@ where
         result.get_result()
     }
 
-    pub async fn get_as_vec_with_filter(
+    pub fn get_as_vec_with_filter(
         &self,
         filter: impl Fn(&TMyNoSqlEntity) -> bool,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let read_access = self.data.read().await;
+        let read_access = self.data.read().unwrap();
         let mut result = LazyVec::new();
         for partition in read_access.values() {
             for item in partition.values() {
@@ -148,8 +197,8 @@ where
         result.get_result()
     }
 
-    pub async fn has_partition(&self, partition_key: &str) -> bool {
-        let read_access = self.data.read().await;
+    pub fn has_partition(&self, partition_key: &str) -> bool {
+        let read_access = self.data.read().unwrap();
         read_access.contains_key(partition_key)
     }
 }
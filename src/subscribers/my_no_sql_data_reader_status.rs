@@ -0,0 +1,18 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MyNoSqlDataReaderStatus {
+    // Reader is created but connection is not established yet
+    Pending,
+    // Subscribe is sent. Waiting for the table to be initialized
+    Subscribed,
+    Initialized,
+    // Connection is lost. Data is kept but may be stale
+    Disconnected,
+    TableNotFound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableNotFoundPolicy {
+    #[default]
+    ReturnError,
+    KeepWaiting,
+}
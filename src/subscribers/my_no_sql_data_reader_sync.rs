@@ -0,0 +1,26 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+use super::MyNoSqlTableSnapshot;
+
+// Methods carry the _sync suffix so they do not clash with the async methods of the readers
+pub trait MyNoSqlDataReaderSync<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    fn get_table_snapshot_sync(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>>;
+
+    fn get_table_snapshot_as_vec_sync(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>>;
+
+    fn get_by_partition_key_sync(
+        &self,
+        partition_key: &str,
+    ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>>;
+
+    fn get_by_partition_key_as_vec_sync(
+        &self,
+        partition_key: &str,
+    ) -> Option<Vec<Arc<TMyNoSqlEntity>>>;
+
+    fn get_entity_sync(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>>;
+
+    fn has_partition_sync(&self, partition_key: &str) -> bool;
+}
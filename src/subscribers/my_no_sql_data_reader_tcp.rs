@@ -6,26 +6,38 @@ use std::{
 
 use async_trait::async_trait;
 use my_json::json_reader::array_parser::JsonArrayIterator;
-use my_no_sql_core::db_json_entity::DbJsonEntity;
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use my_no_sql_tcp_shared::sync_to_main::SyncToMainNodeHandler;
-use rust_extensions::{ApplicationStates, StrOrString};
+use rust_extensions::{ApplicationStates, Logger, StrOrString};
 use serde::de::DeserializeOwned;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
+use tokio_stream::Stream;
+
+use crate::WaitError;
 
 use super::{
-    GetEntitiesBuilder, GetEntityBuilder, MyNoSqlDataReader, MyNoSqlDataReaderCallBacks,
-    MyNoSqlDataReaderData, UpdateEvent,
+    BrokenEntities, BrokenEntitiesHandler, BrokenEntity, CallbacksDeadLetterHandler,
+    CallbacksOverflowPolicy, CallbacksQueueMetrics, CallbacksRetryPolicy, GetEntitiesBuilder,
+    GetEntitiesByKeysBuilder, GetEntityBuilder, MyNoSqlDataReader,
+    MyNoSqlDataReaderCallBacksPusher, MyNoSqlDataReaderData, MyNoSqlDataReaderEntities,
+    MyNoSqlDataReaderExtendedCallBacks, MyNoSqlDataReaderIndexes, MyNoSqlDataReaderStatus,
+    MyNoSqlDataReaderSync, MyNoSqlDataReaderView, MyNoSqlPartitionSnapshot, MyNoSqlReadView,
+    MyNoSqlTableSnapshot, QueryBuilder, ReaderChange, TableNotFoundPolicy, UnchangedRowsPolicy,
+    UpdateEvent, DEFAULT_CHANGES_CAPACITY,
 };
 
 pub struct MyNoSqlDataReaderInner<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
     data: RwLock<MyNoSqlDataReaderData<TMyNoSqlEntity>>,
+    entities: Arc<MyNoSqlDataReaderEntities<TMyNoSqlEntity>>,
+    indexes: Arc<MyNoSqlDataReaderIndexes<TMyNoSqlEntity>>,
+    callbacks: Arc<MyNoSqlDataReaderCallBacksPusher<TMyNoSqlEntity>>,
     sync_handler: Arc<SyncToMainNodeHandler>,
+    broken_entities: BrokenEntities,
 }
 
 impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> MyNoSqlDataReaderInner<TMyNoSqlEntity> {
-    pub fn get_data(&self) -> &RwLock<MyNoSqlDataReaderData<TMyNoSqlEntity>> {
-        &self.data
+    pub fn get_entities(&self) -> &MyNoSqlDataReaderEntities<TMyNoSqlEntity> {
+        self.entities.as_ref()
     }
 
     pub fn get_sync_handler(&self) -> &Arc<SyncToMainNodeHandler> {
@@ -45,52 +57,120 @@ where
         app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
         sync_handler: Arc<SyncToMainNodeHandler>,
     ) -> Self {
+        let data = MyNoSqlDataReaderData::new(TMyNoSqlEntity::TABLE_NAME, app_states).await;
+        let entities = data.get_entities();
+        let indexes = data.get_indexes();
+        let callbacks = data.get_callbacks();
+
         Self {
             inner: Arc::new(MyNoSqlDataReaderInner {
-                data: RwLock::new(
-                    MyNoSqlDataReaderData::new(TMyNoSqlEntity::TABLE_NAME, app_states).await,
-                ),
+                data: RwLock::new(data),
+                entities,
+                indexes,
+                callbacks,
                 sync_handler,
+                broken_entities: BrokenEntities::new(),
             }),
         }
     }
 
-    pub async fn get_table_snapshot(
-        &self,
-    ) -> Option<BTreeMap<String, BTreeMap<String, Arc<TMyNoSqlEntity>>>> {
-        let reader = self.inner.data.read().await;
-        return reader.get_table_snapshot();
+    pub async fn get_table_snapshot(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.inner.entities.get_table_snapshot()
+    }
+
+    // Same version as the one callbacks receive for the latest applied update
+    pub async fn get_read_view(&self) -> MyNoSqlReadView<TMyNoSqlEntity> {
+        let read_access = self.inner.data.read().await;
+        read_access.get_read_view()
     }
 
     pub async fn get_table_snapshot_as_vec(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let reader = self.inner.data.read().await;
-        reader.get_table_snapshot_as_vec()
+        self.inner.entities.get_table_snapshot_as_vec()
     }
 
     pub async fn assign_callback<
-        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
+        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
+    >(
+        &self,
+        callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
+    ) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.assign_callback(callbacks, false);
+    }
+
+    // Current table content is delivered to the callbacks as inserted rows before any further update
+    pub async fn assign_callback_with_replay<
+        TMyNoSqlDataReaderCallBacks: MyNoSqlDataReaderExtendedCallBacks<TMyNoSqlEntity> + Send + Sync + 'static,
     >(
         &self,
         callbacks: Arc<TMyNoSqlDataReaderCallBacks>,
     ) {
         let mut write_access = self.inner.data.write().await;
-        write_access.assign_callback(callbacks).await;
+        write_access.assign_callback(callbacks, true);
+    }
+
+    pub async fn add_index(
+        &self,
+        name: impl Into<String>,
+        get_key: impl Fn(&TMyNoSqlEntity) -> String + Send + Sync + 'static,
+    ) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.add_index(name.into(), Box::new(get_key));
+    }
+
+    pub async fn subscribe_changes(&self) -> impl Stream<Item = ReaderChange<TMyNoSqlEntity>> {
+        self.subscribe_changes_with_capacity(DEFAULT_CHANGES_CAPACITY)
+            .await
+    }
+
+    // Subscriber which falls behind by more than capacity changes gets Lagged and the whole table
+    pub async fn subscribe_changes_with_capacity(
+        &self,
+        capacity: usize,
+    ) -> impl Stream<Item = ReaderChange<TMyNoSqlEntity>> {
+        let mut write_access = self.inner.data.write().await;
+        write_access.subscribe_changes(capacity)
+    }
+
+    // View is updated while the returned Arc is held
+    pub async fn create_view<TView: Send + Sync + 'static>(
+        &self,
+        map: impl Fn(&TMyNoSqlEntity) -> Option<TView> + Send + Sync + 'static,
+    ) -> Arc<MyNoSqlDataReaderView<TMyNoSqlEntity, TView>> {
+        let mut write_access = self.inner.data.write().await;
+        write_access.create_view(Box::new(map))
+    }
+
+    pub async fn get_by_index(&self, name: &str, key: &str) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.inner.indexes.get_by_index(name, key)
     }
 
     pub async fn get_by_partition_key(
         &self,
         partition_key: &str,
     ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
-        let reader = self.inner.data.read().await;
-        reader.get_by_partition(partition_key)
+        self.inner.entities.get_by_partition(partition_key)
+    }
+
+    pub async fn get_partition_snapshot(
+        &self,
+        partition_key: &str,
+    ) -> Option<MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        self.inner.entities.get_partition_snapshot(partition_key)
+    }
+
+    pub async fn get_partitions_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        self.inner.entities.get_partitions_with_prefix(prefix)
     }
 
     pub async fn get_by_partition_key_as_vec(
         &self,
         partition_key: &str,
     ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
-        let reader = self.inner.data.read().await;
-        reader.get_by_partition_as_vec(partition_key)
+        self.inner.entities.get_by_partition_as_vec(partition_key)
     }
 
     pub async fn get_entity(
@@ -98,8 +178,7 @@ where
         partition_key: &str,
         row_key: &str,
     ) -> Option<Arc<TMyNoSqlEntity>> {
-        let reader = self.inner.data.read().await;
-        reader.get_entity(partition_key, row_key)
+        self.inner.entities.get_entity(partition_key, row_key)
     }
 
     pub fn get_entities<'s>(
@@ -117,53 +196,68 @@ where
         GetEntityBuilder::new(partition_key, row_key, self.inner.clone())
     }
 
+    pub async fn get_entities_by_keys(&self, keys: &[(&str, &str)]) -> Vec<Arc<TMyNoSqlEntity>> {
+        self.inner.entities.get_entities_by_keys(keys)
+    }
+
+    pub async fn get_partitions(
+        &self,
+        partition_keys: &[&str],
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        self.inner.entities.get_partitions(partition_keys)
+    }
+
+    pub fn get_entities_by_keys_with_callback_to_server<'s>(
+        &'s self,
+        keys: &'s [(&'s str, &'s str)],
+    ) -> GetEntitiesByKeysBuilder<'s, TMyNoSqlEntity> {
+        GetEntitiesByKeysBuilder::new(keys, self.inner.clone())
+    }
+
+    pub fn query(&self) -> QueryBuilder<TMyNoSqlEntity> {
+        QueryBuilder::new(self.inner.clone())
+    }
+
     pub async fn has_partition(&self, partition_key: &str) -> bool {
-        let reader: tokio::sync::RwLockReadGuard<'_, MyNoSqlDataReaderData<TMyNoSqlEntity>> =
-            self.inner.data.read().await;
-        reader.has_partition(partition_key)
+        self.inner.entities.has_partition(partition_key)
     }
 
-    pub fn deserialize_entity<'s>(&self, data: &[u8]) -> TMyNoSqlEntity {
+    pub fn deserialize_entity(&self, data: &[u8]) -> Result<TMyNoSqlEntity, BrokenEntity> {
         let parse_result: Result<TMyNoSqlEntity, _> = serde_json::from_slice(&data);
 
         match parse_result {
-            Ok(el) => return el,
-            Err(err) => {
-                let db_entity = DbJsonEntity::parse(data);
-
-                match db_entity {
-                    Ok(db_entity) => {
-                        panic!(
-                            "Table: {}. Can not parse entity with PartitionKey: [{}] and RowKey: [{}]. Err: {:?}",
-                             TMyNoSqlEntity::TABLE_NAME, db_entity.partition_key, db_entity.row_key, err
-                        );
-                    }
-                    Err(err) => {
-                        panic!(
-                            "Table: {}. Can not extract partitionKey and rowKey. Looks like entity broken at all. Err: {:?}",
-                            TMyNoSqlEntity::TABLE_NAME, err
-                        )
-                    }
-                }
-            }
+            Ok(el) => Ok(el),
+            Err(err) => Err(BrokenEntity::new(data, format!("{:?}", err))),
         }
     }
 
-    pub fn deserialize_array(&self, data: &[u8]) -> HashMap<String, Vec<TMyNoSqlEntity>> {
+    // Broken array rejects the whole frame, since the rows after the broken place are unknown.
+    // Rows which can not be deserialized are returned separately to be quarantined
+    pub fn deserialize_array(
+        &self,
+        data: &[u8],
+    ) -> Result<(HashMap<String, Vec<TMyNoSqlEntity>>, Vec<BrokenEntity>), String> {
         let mut result = HashMap::new();
+        let mut broken_entities = Vec::new();
 
         for db_entity in JsonArrayIterator::new(data) {
-            if let Err(err) = &db_entity {
-                panic!(
-                    "Table: {}. The whole array of json entities is broken. Err: {:?}",
-                    TMyNoSqlEntity::TABLE_NAME,
-                    err
-                );
-            }
-
-            let db_entity_data = db_entity.unwrap();
+            let db_entity_data = match db_entity {
+                Ok(db_entity_data) => db_entity_data,
+                Err(err) => {
+                    return Err(format!(
+                        "The whole array of json entities is broken. Err: {:?}",
+                        err
+                    ));
+                }
+            };
 
-            let el = self.deserialize_entity(db_entity_data);
+            let el = match self.deserialize_entity(db_entity_data) {
+                Ok(el) => el,
+                Err(broken_entity) => {
+                    broken_entities.push(broken_entity);
+                    continue;
+                }
+            };
 
             let partition_key = el.get_partition_key();
             if !result.contains_key(partition_key) {
@@ -173,19 +267,126 @@ where
             result.get_mut(partition_key).unwrap().push(el);
         }
 
-        result
+        Ok((result, broken_entities))
+    }
+
+    fn quarantine(&self, broken_entities: Vec<BrokenEntity>) {
+        if !broken_entities.is_empty() {
+            self.inner
+                .broken_entities
+                .quarantine(TMyNoSqlEntity::TABLE_NAME, broken_entities);
+        }
+    }
+
+    fn reject_frame(&self, contract_name: &str, err: String) {
+        my_logger::LOGGER.write_error(
+            "MyNoSqlDataReader".to_string(),
+            format!(
+                "Table: {}. {} is rejected, previous data is kept. {}",
+                TMyNoSqlEntity::TABLE_NAME,
+                contract_name,
+                err
+            ),
+            None,
+        );
+    }
+
+    pub fn get_broken_entities(&self) -> Vec<BrokenEntity> {
+        self.inner.broken_entities.get_all()
+    }
+
+    pub fn set_broken_entities_handler(
+        &self,
+        handler: Arc<dyn BrokenEntitiesHandler + Send + Sync + 'static>,
+    ) {
+        self.inner.broken_entities.set_handler(handler);
+    }
+
+    pub async fn get_status(&self) -> MyNoSqlDataReaderStatus {
+        let reader = self.inner.data.read().await;
+        reader.get_status()
+    }
+
+    pub async fn subscribe_to_status(&self) -> watch::Receiver<MyNoSqlDataReaderStatus> {
+        let reader = self.inner.data.read().await;
+        reader.subscribe_to_status()
+    }
+
+    pub async fn set_table_not_found_policy(&self, policy: TableNotFoundPolicy) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.set_table_not_found_policy(policy);
+    }
+
+    pub async fn set_callbacks_max_batch_size(&self, value: usize) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.set_callbacks_max_batch_size(value);
+    }
+
+    // Does not wait for the write lock, so a reader update blocked by the full queue can be released
+    pub fn set_callbacks_queue_capacity(
+        &self,
+        capacity: usize,
+        overflow_policy: CallbacksOverflowPolicy,
+    ) {
+        self.inner
+            .callbacks
+            .set_queue_capacity(capacity, overflow_policy);
+    }
+
+    pub fn get_callbacks_queue_metrics(&self) -> Vec<CallbacksQueueMetrics> {
+        self.inner.callbacks.get_queue_metrics()
+    }
+
+    pub fn set_callbacks_retry_policy(&self, retry_policy: CallbacksRetryPolicy) {
+        self.inner.callbacks.set_retry_policy(retry_policy);
+    }
+
+    pub fn set_callbacks_dead_letter_handler(
+        &self,
+        handler: Arc<dyn CallbacksDeadLetterHandler<TMyNoSqlEntity> + Send + Sync + 'static>,
+    ) {
+        self.inner.callbacks.set_dead_letter_handler(handler);
+    }
+
+    pub async fn set_unchanged_rows_policy(&self, policy: UnchangedRowsPolicy) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.set_unchanged_rows_policy(policy);
     }
 
-    pub async fn wait_until_first_data_arrives(&self) {
+    pub async fn wait_until_first_data_arrives(&self) -> Result<(), WaitError> {
+        let mut status = self.subscribe_to_status().await;
+
         loop {
             {
                 let reader = self.inner.data.read().await;
                 if reader.has_entities_at_all().await {
-                    return;
+                    return Ok(());
+                }
+
+                if reader.get_status() == MyNoSqlDataReaderStatus::TableNotFound
+                    && reader.get_table_not_found_policy() == TableNotFoundPolicy::ReturnError
+                {
+                    return Err(WaitError::TableNotFound {
+                        table_name: TMyNoSqlEntity::TABLE_NAME.to_string(),
+                    });
                 }
             }
 
-            tokio::time::sleep(Duration::from_millis(100)).await;
+            if status.changed().await.is_err() {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+
+    pub async fn wait_until_first_data_arrives_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(), WaitError> {
+        match tokio::time::timeout(timeout, self.wait_until_first_data_arrives()).await {
+            Ok(result) => result,
+            Err(_) => Err(WaitError::Timeout {
+                table_names: vec![TMyNoSqlEntity::TABLE_NAME.to_string()],
+            }),
         }
     }
 }
@@ -195,29 +396,114 @@ impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + DeserializeOwned> UpdateEvent
     for MyNoSqlDataReaderTcp<TMyNoSqlEntity>
 {
     async fn init_table(&self, data: Vec<u8>) {
-        let data = self.deserialize_array(data.as_slice());
+        let (data, broken_entities) = match self.deserialize_array(data.as_slice()) {
+            Ok(result) => result,
+            Err(err) => {
+                self.reject_frame("InitTable", err);
+                return;
+            }
+        };
 
-        let mut write_access = self.inner.data.write().await;
-        write_access.init_table(data).await;
+        self.inner.broken_entities.clear();
+        self.quarantine(broken_entities);
+
+        {
+            let mut write_access = self.inner.data.write().await;
+            write_access.init_table(data).await;
+        }
+
+        self.inner.callbacks.push_flushed().await;
     }
 
     async fn init_partition(&self, partition_key: &str, data: Vec<u8>) {
-        let data = self.deserialize_array(data.as_slice());
+        let (data, broken_entities) = match self.deserialize_array(data.as_slice()) {
+            Ok(result) => result,
+            Err(err) => {
+                self.reject_frame("InitPartition", err);
+                return;
+            }
+        };
 
-        let mut write_access = self.inner.data.write().await;
-        write_access.init_partition(partition_key, data).await;
+        self.inner.broken_entities.release_partition(partition_key);
+        self.quarantine(broken_entities);
+
+        {
+            let mut write_access = self.inner.data.write().await;
+            write_access.init_partition(partition_key, data).await;
+        }
+
+        self.inner.callbacks.push_flushed().await;
     }
 
     async fn update_rows(&self, data: Vec<u8>) {
-        let data = self.deserialize_array(data.as_slice());
+        let (data, broken_entities) = match self.deserialize_array(data.as_slice()) {
+            Ok(result) => result,
+            Err(err) => {
+                self.reject_frame("UpdateRows", err);
+                return;
+            }
+        };
+
+        self.inner.broken_entities.release(
+            data.values()
+                .flatten()
+                .map(|entity| (entity.get_partition_key(), entity.get_row_key())),
+        );
+        self.quarantine(broken_entities);
+
+        {
+            let mut write_access = self.inner.data.write().await;
+            write_access.update_rows(data).await;
+        }
 
-        let mut write_access = self.inner.data.write().await;
-        write_access.update_rows(data);
+        self.inner.callbacks.push_flushed().await;
     }
 
     async fn delete_rows(&self, rows_to_delete: Vec<my_no_sql_tcp_shared::DeleteRowTcpContract>) {
+        self.inner.broken_entities.release(
+            rows_to_delete
+                .iter()
+                .map(|row| (row.partition_key.as_str(), row.row_key.as_str())),
+        );
+
+        {
+            let mut write_access = self.inner.data.write().await;
+            write_access.delete_rows(rows_to_delete).await;
+        }
+
+        self.inner.callbacks.push_flushed().await;
+    }
+
+    async fn table_not_found(&self) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.table_not_found();
+    }
+
+    async fn subscribed(&self) {
         let mut write_access = self.inner.data.write().await;
-        write_access.delete_rows(rows_to_delete);
+        write_access.subscribed();
+    }
+
+    async fn disconnected(&self) {
+        let mut write_access = self.inner.data.write().await;
+        write_access.disconnected();
+    }
+
+    async fn unsubscribed(&self) {
+        {
+            let mut write_access = self.inner.data.write().await;
+            write_access.unsubscribed().await;
+        }
+
+        self.inner.callbacks.push_flushed().await;
+    }
+
+    async fn has_entities_at_all(&self) -> bool {
+        self.inner.entities.has_entities_at_all()
+    }
+
+    async fn wait_until_first_data_arrives(&self) -> Result<(), WaitError> {
+        self.wait_until_first_data_arrives().await
     }
 }
 
@@ -263,4 +549,61 @@ where
     async fn has_partition(&self, partition_key: &str) -> bool {
         self.has_partition(partition_key).await
     }
+
+    async fn get_entities_by_keys(&self, keys: &[(&str, &str)]) -> Vec<Arc<TMyNoSqlEntity>> {
+        self.get_entities_by_keys(keys).await
+    }
+
+    async fn get_partitions(
+        &self,
+        partition_keys: &[&str],
+    ) -> BTreeMap<String, MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        self.get_partitions(partition_keys).await
+    }
+
+    fn get_entities_by_keys_with_callback_to_server<'s>(
+        &'s self,
+        keys: &'s [(&'s str, &'s str)],
+    ) -> GetEntitiesByKeysBuilder<'s, TMyNoSqlEntity> {
+        self.get_entities_by_keys_with_callback_to_server(keys)
+    }
+
+    fn query(&self) -> QueryBuilder<TMyNoSqlEntity> {
+        self.query()
+    }
+}
+
+impl<TMyNoSqlEntity> MyNoSqlDataReaderSync<TMyNoSqlEntity> for MyNoSqlDataReaderTcp<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + DeserializeOwned + 'static,
+{
+    fn get_table_snapshot_sync(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.inner.entities.get_table_snapshot()
+    }
+
+    fn get_table_snapshot_as_vec_sync(&self) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.inner.entities.get_table_snapshot_as_vec()
+    }
+
+    fn get_by_partition_key_sync(
+        &self,
+        partition_key: &str,
+    ) -> Option<BTreeMap<String, Arc<TMyNoSqlEntity>>> {
+        self.inner.entities.get_by_partition(partition_key)
+    }
+
+    fn get_by_partition_key_as_vec_sync(
+        &self,
+        partition_key: &str,
+    ) -> Option<Vec<Arc<TMyNoSqlEntity>>> {
+        self.inner.entities.get_by_partition_as_vec(partition_key)
+    }
+
+    fn get_entity_sync(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
+        self.inner.entities.get_entity(partition_key, row_key)
+    }
+
+    fn has_partition_sync(&self, partition_key: &str) -> bool {
+        self.inner.entities.has_partition(partition_key)
+    }
 }
@@ -0,0 +1,195 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+use super::{MyNoSqlDataReaderCallBacks, MyNoSqlTableEntities};
+
+// Projection of the reader table. Updated by the reader under the same write lock as the table itself.
+pub struct MyNoSqlDataReaderView<
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+    TView: Send + Sync + 'static,
+> {
+    map: Box<dyn Fn(&TMyNoSqlEntity) -> Option<TView> + Send + Sync + 'static>,
+    items: RwLock<BTreeMap<String, BTreeMap<String, Arc<TView>>>>,
+}
+
+impl<TMyNoSqlEntity, TView> MyNoSqlDataReaderView<TMyNoSqlEntity, TView>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+    TView: Send + Sync + 'static,
+{
+    pub fn new(map: Box<dyn Fn(&TMyNoSqlEntity) -> Option<TView> + Send + Sync + 'static>) -> Self {
+        Self {
+            map,
+            items: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub(crate) fn init(&self, table: &MyNoSqlTableEntities<TMyNoSqlEntity>) {
+        let mut items = BTreeMap::new();
+
+        for (partition_key, partition) in table {
+            let mut by_partition = BTreeMap::new();
+
+            for (row_key, entity) in partition.iter() {
+                if let Some(view) = (self.map)(entity) {
+                    by_partition.insert(row_key.to_string(), Arc::new(view));
+                }
+            }
+
+            if !by_partition.is_empty() {
+                items.insert(partition_key.to_string(), by_partition);
+            }
+        }
+
+        *self.items.write().unwrap() = items;
+    }
+
+    pub fn get(&self, partition_key: &str, row_key: &str) -> Option<Arc<TView>> {
+        let items = self.items.read().unwrap();
+        let partition = items.get(partition_key)?;
+        partition.get(row_key).cloned()
+    }
+
+    pub fn get_by_partition_key(&self, partition_key: &str) -> Option<Vec<Arc<TView>>> {
+        let items = self.items.read().unwrap();
+        let partition = items.get(partition_key)?;
+        Some(partition.values().cloned().collect())
+    }
+
+    pub fn get_all(&self) -> Vec<Arc<TView>> {
+        let items = self.items.read().unwrap();
+
+        let mut result = Vec::new();
+
+        for partition in items.values() {
+            result.extend(partition.values().cloned());
+        }
+
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        let items = self.items.read().unwrap();
+        items.values().map(|partition| partition.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.read().unwrap().is_empty()
+    }
+}
+
+#[async_trait::async_trait]
+impl<TMyNoSqlEntity, TView> MyNoSqlDataReaderCallBacks<TMyNoSqlEntity>
+    for MyNoSqlDataReaderView<TMyNoSqlEntity, TView>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+    TView: Send + Sync + 'static,
+{
+    async fn inserted_or_replaced(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
+        let mut items = self.items.write().unwrap();
+
+        let partition = items
+            .entry(partition_key.to_string())
+            .or_insert_with(BTreeMap::new);
+
+        for entity in entities {
+            match (self.map)(entity.as_ref()) {
+                Some(view) => {
+                    partition.insert(entity.get_row_key().to_string(), Arc::new(view));
+                }
+                None => {
+                    partition.remove(entity.get_row_key());
+                }
+            }
+        }
+
+        if partition.is_empty() {
+            items.remove(partition_key);
+        }
+    }
+
+    async fn deleted(&self, partition_key: &str, entities: Vec<Arc<TMyNoSqlEntity>>) {
+        let mut items = self.items.write().unwrap();
+
+        let mut remove_partition = false;
+
+        if let Some(partition) = items.get_mut(partition_key) {
+            for entity in entities {
+                partition.remove(entity.get_row_key());
+            }
+
+            remove_partition = partition.is_empty();
+        }
+
+        if remove_partition {
+            items.remove(partition_key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use super::MyNoSqlDataReaderView;
+    use crate::subscribers::{
+        callback_triggers, test_row::TestRow, MyNoSqlTableEntities, UnchangedRowsPolicy,
+    };
+
+    fn create_partition(rows: &[(&str, &str)]) -> Arc<BTreeMap<String, Arc<TestRow>>> {
+        let mut result = BTreeMap::new();
+
+        for (row_key, value) in rows {
+            result.insert(
+                row_key.to_string(),
+                Arc::new(TestRow::with_value("PK", row_key, value)),
+            );
+        }
+
+        Arc::new(result)
+    }
+
+    #[tokio::test]
+    async fn test_view_follows_table_reinit() {
+        let view = MyNoSqlDataReaderView::new(Box::new(|itm: &TestRow| {
+            if itm.value.is_empty() {
+                None
+            } else {
+                Some(itm.value.clone())
+            }
+        }));
+
+        let mut before = MyNoSqlTableEntities::new();
+        before.insert(
+            "PK".to_string(),
+            create_partition(&[("RK1", "1"), ("RK2", "2")]),
+        );
+        let before = Arc::new(before);
+
+        view.init(before.as_ref());
+        assert_eq!(2, view.len());
+
+        let mut now = MyNoSqlTableEntities::new();
+        now.insert(
+            "PK".to_string(),
+            create_partition(&[("RK1", ""), ("RK3", "3")]),
+        );
+
+        callback_triggers::trigger_table_difference(
+            &view,
+            Some(before),
+            &now,
+            UnchangedRowsPolicy::Skip,
+        )
+        .await;
+
+        assert_eq!(1, view.len());
+        assert_eq!("3", view.get("PK", "RK3").unwrap().as_str());
+        assert!(view.get("PK", "RK1").is_none());
+        assert!(view.get("PK", "RK2").is_none());
+    }
+}
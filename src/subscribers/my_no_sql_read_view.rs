@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+use super::{MyNoSqlPartitionSnapshot, MyNoSqlTableSnapshot};
+
+// Table exactly as it was right after the reader applied one incoming update.
+// Version grows by one on every change of the reader table.
+pub struct MyNoSqlReadView<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> {
+    pub version: u64,
+    table: Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static> Clone
+    for MyNoSqlReadView<TMyNoSqlEntity>
+{
+    fn clone(&self) -> Self {
+        Self {
+            version: self.version,
+            table: self.table.clone(),
+        }
+    }
+}
+
+impl<TMyNoSqlEntity> MyNoSqlReadView<TMyNoSqlEntity>
+where
+    TMyNoSqlEntity: MyNoSqlEntity + Send + Sync + 'static,
+{
+    pub fn new(version: u64, table: Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>>) -> Self {
+        Self { version, table }
+    }
+
+    pub fn get_table_snapshot(&self) -> Option<MyNoSqlTableSnapshot<TMyNoSqlEntity>> {
+        self.table.clone()
+    }
+
+    pub fn get_partition_snapshot(
+        &self,
+        partition_key: &str,
+    ) -> Option<MyNoSqlPartitionSnapshot<TMyNoSqlEntity>> {
+        self.table.as_ref()?.get(partition_key).cloned()
+    }
+
+    pub fn get_by_partition_as_vec(&self, partition_key: &str) -> Vec<Arc<TMyNoSqlEntity>> {
+        match self.get_partition_snapshot(partition_key) {
+            Some(partition) => partition.values().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn get_entity(&self, partition_key: &str, row_key: &str) -> Option<Arc<TMyNoSqlEntity>> {
+        self.table
+            .as_ref()?
+            .get(partition_key)?
+            .get(row_key)
+            .cloned()
+    }
+}
@@ -0,0 +1,10 @@
+mod query_builder;
+pub use query_builder::*;
+mod query_builder_inner;
+pub use query_builder_inner::*;
+#[cfg(feature = "mocks")]
+mod query_builder_mock;
+#[cfg(feature = "mocks")]
+pub use query_builder_mock::*;
+mod query_filter;
+pub use query_filter::*;
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+#[cfg(feature = "mocks")]
+use super::QueryBuilderMock;
+use super::{
+    super::my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner, QueryBuilderInner, QueryFilter,
+};
+
+pub enum QueryBuilder<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    Inner(QueryBuilderInner<TMyNoSqlEntity>),
+    #[cfg(feature = "mocks")]
+    Mock(QueryBuilderMock<TMyNoSqlEntity>),
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> QueryBuilder<TMyNoSqlEntity> {
+    pub fn new(inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>) -> Self {
+        Self::Inner(QueryBuilderInner::new(inner))
+    }
+
+    #[cfg(feature = "mocks")]
+    pub fn new_mock(
+        inner: Arc<crate::subscribers::MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
+    ) -> Self {
+        Self::Mock(QueryBuilderMock::new(inner))
+    }
+
+    fn get_query_filter_mut(&mut self) -> &mut QueryFilter<TMyNoSqlEntity> {
+        match self {
+            QueryBuilder::Inner(inner) => inner.get_query_filter_mut(),
+            #[cfg(feature = "mocks")]
+            QueryBuilder::Mock(inner) => inner.get_query_filter_mut(),
+        }
+    }
+
+    pub fn set_partition_key_filter(
+        mut self,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.get_query_filter_mut().partition_key_filter = Some(Box::new(filter));
+        self
+    }
+
+    pub fn set_filter(
+        mut self,
+        filter: impl Fn(&TMyNoSqlEntity) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.get_query_filter_mut().filter = Some(Box::new(filter));
+        self
+    }
+
+    pub fn set_limit(mut self, value: usize) -> Self {
+        self.get_query_filter_mut().limit = Some(value);
+        self
+    }
+
+    pub fn set_partition_last_read_moment(mut self) -> Self {
+        match &mut self {
+            QueryBuilder::Inner(inner) => inner.set_partition_last_read_moment(),
+            #[cfg(feature = "mocks")]
+            QueryBuilder::Mock(inner) => inner.set_partition_last_read_moment(),
+        }
+
+        self
+    }
+
+    pub fn set_row_last_read_moment(mut self) -> Self {
+        match &mut self {
+            QueryBuilder::Inner(inner) => inner.set_row_last_read_moment(),
+            #[cfg(feature = "mocks")]
+            QueryBuilder::Mock(inner) => inner.set_row_last_read_moment(),
+        }
+
+        self
+    }
+
+    pub fn set_partition_expiration_moment(
+        mut self,
+        value: Option<DateTimeAsMicroseconds>,
+    ) -> Self {
+        match &mut self {
+            QueryBuilder::Inner(inner) => inner.set_partition_expiration_moment(value),
+            #[cfg(feature = "mocks")]
+            QueryBuilder::Mock(inner) => inner.set_partition_expiration_moment(value),
+        }
+
+        self
+    }
+
+    pub fn set_row_expiration_moment(mut self, value: Option<DateTimeAsMicroseconds>) -> Self {
+        match &mut self {
+            QueryBuilder::Inner(inner) => inner.set_row_expiration_moment(value),
+            #[cfg(feature = "mocks")]
+            QueryBuilder::Mock(inner) => inner.set_row_expiration_moment(value),
+        }
+
+        self
+    }
+
+    pub async fn execute(&self) -> Vec<Arc<TMyNoSqlEntity>> {
+        match self {
+            QueryBuilder::Inner(inner) => inner.execute().await,
+            #[cfg(feature = "mocks")]
+            QueryBuilder::Mock(inner) => inner.execute().await,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::{
+    super::{my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner, update_statistics},
+    QueryFilter,
+};
+
+pub struct QueryBuilderInner<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    query_filter: QueryFilter<TMyNoSqlEntity>,
+    update_statistic_data: UpdateEntityStatisticsData,
+    inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> QueryBuilderInner<TMyNoSqlEntity> {
+    pub fn new(inner: Arc<MyNoSqlDataReaderInner<TMyNoSqlEntity>>) -> Self {
+        Self {
+            query_filter: QueryFilter::new(),
+            update_statistic_data: UpdateEntityStatisticsData::default(),
+            inner,
+        }
+    }
+
+    pub fn get_query_filter_mut(&mut self) -> &mut QueryFilter<TMyNoSqlEntity> {
+        &mut self.query_filter
+    }
+
+    pub fn set_partition_last_read_moment(&mut self) {
+        self.update_statistic_data.partition_last_read_moment = true;
+    }
+
+    pub fn set_row_last_read_moment(&mut self) {
+        self.update_statistic_data.row_last_read_moment = true;
+    }
+
+    pub fn set_partition_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.partition_expiration_moment = Some(value);
+    }
+
+    pub fn set_row_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.row_expiration_moment = Some(value);
+    }
+
+    pub async fn execute(&self) -> Vec<Arc<TMyNoSqlEntity>> {
+        let table = match self.inner.get_entities().get_table_snapshot() {
+            Some(table) => table,
+            None => return vec![],
+        };
+
+        let result = self.query_filter.select(
+            table
+                .iter()
+                .map(|(partition_key, partition)| (partition_key, partition.as_ref())),
+        );
+
+        update_statistics::update_statistics(
+            self.inner.as_ref(),
+            &result,
+            &self.update_statistic_data,
+        )
+        .await;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use my_no_sql_tcp_shared::sync_to_main::SyncToMainNodeHandler;
+
+    use crate::subscribers::{
+        test_row::{TestAppStates, TestRow},
+        MyNoSqlDataReaderTcp,
+    };
+
+    #[tokio::test]
+    async fn test_uninitialized_table() {
+        let reader: MyNoSqlDataReaderTcp<TestRow> = MyNoSqlDataReaderTcp::new(
+            Arc::new(TestAppStates),
+            Arc::new(SyncToMainNodeHandler::new()),
+        )
+        .await;
+
+        let result = reader
+            .query()
+            .set_partition_last_read_moment()
+            .set_limit(3)
+            .execute()
+            .await;
+
+        assert!(result.is_empty());
+    }
+}
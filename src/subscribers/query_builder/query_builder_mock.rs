@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::QueryFilter;
+use crate::subscribers::MyNoSqlDataReaderMockInner;
+
+pub struct QueryBuilderMock<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    query_filter: QueryFilter<TMyNoSqlEntity>,
+    update_statistic_data: UpdateEntityStatisticsData,
+    inner: Arc<MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> QueryBuilderMock<TMyNoSqlEntity> {
+    pub fn new(inner: Arc<MyNoSqlDataReaderMockInner<TMyNoSqlEntity>>) -> Self {
+        Self {
+            query_filter: QueryFilter::new(),
+            update_statistic_data: UpdateEntityStatisticsData::default(),
+            inner,
+        }
+    }
+
+    pub fn get_query_filter_mut(&mut self) -> &mut QueryFilter<TMyNoSqlEntity> {
+        &mut self.query_filter
+    }
+
+    pub fn set_partition_last_read_moment(&mut self) {
+        self.update_statistic_data.partition_last_read_moment = true;
+    }
+
+    pub fn set_row_last_read_moment(&mut self) {
+        self.update_statistic_data.row_last_read_moment = true;
+    }
+
+    pub fn set_partition_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.partition_expiration_moment = Some(value);
+    }
+
+    pub fn set_row_expiration_moment(&mut self, value: Option<DateTimeAsMicroseconds>) {
+        self.update_statistic_data.row_expiration_moment = Some(value);
+    }
+
+    // Mock does not notify the server, the requested statistics can be checked by the test instead
+    pub fn get_update_statistic_data(&self) -> &UpdateEntityStatisticsData {
+        &self.update_statistic_data
+    }
+
+    pub async fn execute(&self) -> Vec<Arc<TMyNoSqlEntity>> {
+        let read_access = self.inner.data.read().unwrap();
+        self.query_filter.select(read_access.iter())
+    }
+}
@@ -0,0 +1,115 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+
+pub struct QueryFilter<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> {
+    pub partition_key_filter: Option<Box<dyn Fn(&str) -> bool + Send + Sync + 'static>>,
+    pub filter: Option<Box<dyn Fn(&TMyNoSqlEntity) -> bool + Send + Sync + 'static>>,
+    pub limit: Option<usize>,
+}
+
+impl<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static> QueryFilter<TMyNoSqlEntity> {
+    pub fn new() -> Self {
+        Self {
+            partition_key_filter: None,
+            filter: None,
+            limit: None,
+        }
+    }
+
+    pub fn select<'s>(
+        &self,
+        partitions: impl Iterator<Item = (&'s String, &'s BTreeMap<String, Arc<TMyNoSqlEntity>>)>,
+    ) -> Vec<Arc<TMyNoSqlEntity>> {
+        let mut result = Vec::new();
+
+        for (partition_key, partition) in partitions {
+            if let Some(partition_key_filter) = self.partition_key_filter.as_ref() {
+                if !partition_key_filter(partition_key) {
+                    continue;
+                }
+            }
+
+            for entity in partition.values() {
+                if let Some(limit) = self.limit {
+                    if result.len() >= limit {
+                        return result;
+                    }
+                }
+
+                if let Some(filter) = self.filter.as_ref() {
+                    if !filter(entity) {
+                        continue;
+                    }
+                }
+
+                result.push(entity.clone());
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use super::QueryFilter;
+    use crate::subscribers::test_row::TestRow;
+
+    fn create_table() -> BTreeMap<String, BTreeMap<String, Arc<TestRow>>> {
+        let mut table = BTreeMap::new();
+
+        for partition_key in ["PK1", "PK2", "PK3"] {
+            let mut partition = BTreeMap::new();
+
+            for row_key in ["RK1", "RK2", "RK3"] {
+                let row = TestRow::with_value(partition_key, row_key, row_key);
+                partition.insert(row_key.to_string(), Arc::new(row));
+            }
+
+            table.insert(partition_key.to_string(), partition);
+        }
+
+        table
+    }
+
+    fn get_keys(rows: Vec<Arc<TestRow>>) -> Vec<String> {
+        rows.iter()
+            .map(|row| format!("{}/{}", row.partition_key, row.row_key))
+            .collect()
+    }
+
+    #[test]
+    fn test_partition_filter_predicate_and_limit() {
+        let table = create_table();
+
+        let mut query_filter: QueryFilter<TestRow> = QueryFilter::new();
+        query_filter.partition_key_filter = Some(Box::new(|partition_key| partition_key != "PK1"));
+        query_filter.filter = Some(Box::new(|row| row.value != "RK2"));
+        assert_eq!(
+            vec!["PK2/RK1", "PK2/RK3", "PK3/RK1", "PK3/RK3"],
+            get_keys(query_filter.select(table.iter()))
+        );
+
+        query_filter.limit = Some(3);
+        assert_eq!(
+            vec!["PK2/RK1", "PK2/RK3", "PK3/RK1"],
+            get_keys(query_filter.select(table.iter()))
+        );
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let mut query_filter: QueryFilter<TestRow> = QueryFilter::new();
+        query_filter.limit = Some(3);
+
+        let table: BTreeMap<String, BTreeMap<String, Arc<TestRow>>> = BTreeMap::new();
+        assert!(query_filter.select(table.iter()).is_empty());
+
+        let mut table = BTreeMap::new();
+        table.insert("PK1".to_string(), BTreeMap::new());
+        assert!(query_filter.select(table.iter()).is_empty());
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
 
 use my_no_sql_server_abstractions::MyNoSqlEntity;
 use my_no_sql_tcp_shared::sync_to_main::SyncToMainNodeHandler;
@@ -6,10 +10,37 @@ use rust_extensions::ApplicationStates;
 use serde::de::DeserializeOwned;
 use tokio::sync::RwLock;
 
+use crate::GetReaderError;
+
 use super::{MyNoSqlDataReaderTcp, UpdateEvent};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoveReaderResult {
+    // Last holder released the reader. Table is unsubscribed and its data is cleared
+    Unsubscribed,
+    // Reader is still held by the other get_reader callers
+    StillHeld { holders: usize },
+    // Reader of the table was never created or is already removed
+    NotFound,
+}
+
+pub(crate) enum RemovedSubscriber {
+    Last(Arc<dyn UpdateEvent + Send + Sync + 'static>),
+    StillHeld(usize),
+    NotFound,
+}
+
+pub struct SubscriberItem {
+    update_event: Arc<dyn UpdateEvent + Send + Sync + 'static>,
+    reader: Arc<dyn Any + Send + Sync + 'static>,
+    entity_type_id: TypeId,
+    entity_type_name: &'static str,
+    // Amount of get_reader calls not yet released with remove_reader
+    holders: usize,
+}
+
 pub struct Subscribers {
-    subscribers: RwLock<HashMap<String, Arc<dyn UpdateEvent + Send + Sync + 'static>>>,
+    subscribers: RwLock<HashMap<String, SubscriberItem>>,
 }
 
 impl Subscribers {
@@ -19,31 +50,54 @@ impl Subscribers {
         }
     }
 
+    // Returns the reader and true if the reader was created by this call
     pub async fn create_subscriber<TMyNoSqlEntity>(
         &self,
 
         app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
         sync_handler: Arc<SyncToMainNodeHandler>,
-    ) -> Arc<MyNoSqlDataReaderTcp<TMyNoSqlEntity>>
+    ) -> Result<(Arc<MyNoSqlDataReaderTcp<TMyNoSqlEntity>>, bool), GetReaderError>
     where
         TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + DeserializeOwned + 'static,
     {
         let mut write_access = self.subscribers.write().await;
 
-        if write_access.contains_key(TMyNoSqlEntity::TABLE_NAME) {
-            panic!(
-                "You already subscribed for the table {}",
-                TMyNoSqlEntity::TABLE_NAME
-            );
+        if let Some(item) = write_access.get_mut(TMyNoSqlEntity::TABLE_NAME) {
+            if item.entity_type_id != TypeId::of::<TMyNoSqlEntity>() {
+                return Err(GetReaderError::EntityTypeMismatch {
+                    table_name: TMyNoSqlEntity::TABLE_NAME.to_string(),
+                    registered_type: item.entity_type_name,
+                    requested_type: std::any::type_name::<TMyNoSqlEntity>(),
+                });
+            }
+
+            let reader = item
+                .reader
+                .clone()
+                .downcast::<MyNoSqlDataReaderTcp<TMyNoSqlEntity>>()
+                .unwrap();
+
+            item.holders += 1;
+
+            return Ok((reader, false));
         }
 
         let new_reader = MyNoSqlDataReaderTcp::new(app_states, sync_handler).await;
 
         let new_reader = Arc::new(new_reader);
 
-        write_access.insert(TMyNoSqlEntity::TABLE_NAME.to_string(), new_reader.clone());
+        write_access.insert(
+            TMyNoSqlEntity::TABLE_NAME.to_string(),
+            SubscriberItem {
+                update_event: new_reader.clone(),
+                reader: new_reader.clone(),
+                entity_type_id: TypeId::of::<TMyNoSqlEntity>(),
+                entity_type_name: std::any::type_name::<TMyNoSqlEntity>(),
+                holders: 1,
+            },
+        );
 
-        new_reader
+        Ok((new_reader, true))
     }
 
     pub async fn get(
@@ -52,11 +106,35 @@ impl Subscribers {
     ) -> Option<Arc<dyn UpdateEvent + Send + Sync + 'static>> {
         let read_access = self.subscribers.write().await;
         let result = read_access.get(table_name)?;
-        Some(result.clone())
+        Some(result.update_event.clone())
     }
 
-    pub async fn get_tables_to_subscribe(&self) -> Vec<String> {
-        let read_access = self.subscribers.write().await;
-        read_access.keys().map(|itm| itm.to_string()).collect()
+    // Releases one holder. The reader is removed only when the last holder is released
+    pub(crate) async fn remove(&self, table_name: &str) -> RemovedSubscriber {
+        let mut write_access = self.subscribers.write().await;
+
+        let item = match write_access.get_mut(table_name) {
+            Some(item) => item,
+            None => return RemovedSubscriber::NotFound,
+        };
+
+        item.holders -= 1;
+
+        if item.holders > 0 {
+            return RemovedSubscriber::StillHeld(item.holders);
+        }
+
+        match write_access.remove(table_name) {
+            Some(removed) => RemovedSubscriber::Last(removed.update_event),
+            None => RemovedSubscriber::NotFound,
+        }
+    }
+
+    pub async fn get_all(&self) -> Vec<(String, Arc<dyn UpdateEvent + Send + Sync + 'static>)> {
+        let read_access = self.subscribers.read().await;
+        read_access
+            .iter()
+            .map(|(table_name, item)| (table_name.to_string(), item.update_event.clone()))
+            .collect()
     }
 }
@@ -0,0 +1,57 @@
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use rust_extensions::ApplicationStates;
+
+// Entity shared by the tests of the subscribers
+#[derive(serde::Deserialize)]
+pub struct TestRow {
+    pub partition_key: String,
+    pub row_key: String,
+    pub timestamp: i64,
+    pub value: String,
+}
+
+impl TestRow {
+    pub fn new(partition_key: String, row_key: String, timestamp: i64) -> Self {
+        TestRow {
+            partition_key,
+            row_key,
+            timestamp,
+            value: String::new(),
+        }
+    }
+
+    pub fn with_value(partition_key: &str, row_key: &str, value: &str) -> Self {
+        TestRow {
+            partition_key: partition_key.to_string(),
+            row_key: row_key.to_string(),
+            timestamp: 0,
+            value: value.to_string(),
+        }
+    }
+}
+
+impl MyNoSqlEntity for TestRow {
+    const TABLE_NAME: &'static str = "Test";
+
+    fn get_partition_key(&self) -> &str {
+        self.partition_key.as_str()
+    }
+    fn get_row_key(&self) -> &str {
+        self.row_key.as_str()
+    }
+    fn get_time_stamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+pub struct TestAppStates;
+
+impl ApplicationStates for TestAppStates {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        false
+    }
+}
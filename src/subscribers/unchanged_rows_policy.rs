@@ -0,0 +1,8 @@
+// How rows which came with table/partition reinitialization but did not change are reported to callbacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnchangedRowsPolicy {
+    #[default]
+    Skip,
+    // Every row of the reinitialized table/partition is reported, as before the policy was added
+    Emit,
+}
@@ -1,10 +1,18 @@
 use async_trait::async_trait;
 use my_no_sql_tcp_shared::DeleteRowTcpContract;
 
+use crate::WaitError;
+
 #[async_trait]
 pub trait UpdateEvent {
     async fn init_table(&self, data: Vec<u8>);
     async fn init_partition(&self, partition_key: &str, data: Vec<u8>);
     async fn update_rows(&self, data: Vec<u8>);
     async fn delete_rows(&self, rows_to_delete: Vec<DeleteRowTcpContract>);
+    async fn table_not_found(&self);
+    async fn subscribed(&self);
+    async fn disconnected(&self);
+    async fn unsubscribed(&self);
+    async fn has_entities_at_all(&self) -> bool;
+    async fn wait_until_first_data_arrives(&self) -> Result<(), WaitError>;
 }
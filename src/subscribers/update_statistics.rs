@@ -0,0 +1,71 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use my_no_sql_server_abstractions::MyNoSqlEntity;
+use my_no_sql_tcp_shared::sync_to_main::UpdateEntityStatisticsData;
+
+use super::my_no_sql_data_reader_tcp::MyNoSqlDataReaderInner;
+
+// Rows are grouped by partition, so every partition gets a single statistics update
+pub fn group_row_keys_by_partition<TMyNoSqlEntity: MyNoSqlEntity>(
+    entities: &[Arc<TMyNoSqlEntity>],
+) -> BTreeMap<&str, Vec<&str>> {
+    let mut result: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+    for entity in entities {
+        result
+            .entry(entity.get_partition_key())
+            .or_default()
+            .push(entity.get_row_key());
+    }
+
+    result
+}
+
+pub async fn update_statistics<TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + 'static>(
+    inner: &MyNoSqlDataReaderInner<TMyNoSqlEntity>,
+    entities: &[Arc<TMyNoSqlEntity>],
+    update_statistic_data: &UpdateEntityStatisticsData,
+) {
+    for (partition_key, row_keys) in group_row_keys_by_partition(entities) {
+        inner
+            .get_sync_handler()
+            .event_notifier
+            .update(
+                TMyNoSqlEntity::TABLE_NAME,
+                partition_key,
+                || row_keys.iter().copied(),
+                update_statistic_data,
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::subscribers::test_row::TestRow;
+
+    fn create_row(partition_key: &str, row_key: &str) -> Arc<TestRow> {
+        Arc::new(TestRow::new(
+            partition_key.to_string(),
+            row_key.to_string(),
+            0,
+        ))
+    }
+
+    #[test]
+    fn test_rows_are_coalesced_by_partition() {
+        let rows = vec![
+            create_row("PK2", "RK1"),
+            create_row("PK1", "RK1"),
+            create_row("PK2", "RK2"),
+        ];
+
+        let groups = super::group_row_keys_by_partition(&rows);
+
+        assert_eq!(2, groups.len());
+        assert_eq!(&vec!["RK1"], groups.get("PK1").unwrap());
+        assert_eq!(&vec!["RK1", "RK2"], groups.get("PK2").unwrap());
+    }
+}
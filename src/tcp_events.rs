@@ -1,38 +1,205 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
+use my_no_sql_server_abstractions::MyNoSqlEntity;
 use my_no_sql_tcp_shared::{
     sync_to_main::SyncToMainNodeHandler, MyNoSqlReaderTcpSerializer, MyNoSqlTcpContract,
 };
 use my_tcp_sockets::{tcp_connection::SocketConnection, ConnectionEvent, SocketEventCallback};
+use rust_extensions::{ApplicationStates, Logger};
+use serde::de::DeserializeOwned;
+use tokio::sync::{watch, Mutex as AsyncMutex, RwLock};
 
-use crate::subscribers::Subscribers;
+use crate::{
+    endpoint_rotation::EndpointRotation,
+    subscribers::{
+        MyNoSqlDataReaderTcp, RemoveReaderResult, RemovedSubscriber, Subscribers, UpdateEvent,
+    },
+    GetReaderError, MyNoSqlReaderError, ServerErrorPolicy,
+};
 
 pub type TcpConnection = SocketConnection<MyNoSqlTcpContract, MyNoSqlReaderTcpSerializer>;
+
+// Connection which stayed up that long resets the reconnect backoff even without InitTable
+const STABLE_CONNECTION_DURATION: Duration = Duration::from_secs(30);
+
 pub struct TcpEvents {
     app_name: String,
     pub subscribers: Subscribers,
     pub sync_handler: Arc<SyncToMainNodeHandler>,
+    server_error_policy: ServerErrorPolicy,
+    errors: watch::Sender<Option<MyNoSqlReaderError>>,
+    active_connection: RwLock<Option<Arc<TcpConnection>>>,
+    // Subscribe and Unsubscribe of the readers go one by one,
+    // so the server gets them in the order the readers were created and removed
+    subscriptions: AsyncMutex<()>,
+    connect_attempts: AtomicUsize,
+    pub endpoint_rotation: EndpointRotation,
+    first_connect_made: AtomicBool,
+    connected_at: Mutex<Option<Instant>>,
 }
 
 impl TcpEvents {
-    pub fn new(app_name: String, sync_handler: Arc<SyncToMainNodeHandler>) -> Self {
+    pub fn new(
+        app_name: String,
+        sync_handler: Arc<SyncToMainNodeHandler>,
+        server_error_policy: ServerErrorPolicy,
+    ) -> Self {
+        let (errors, _) = watch::channel(None);
         Self {
             app_name,
             subscribers: Subscribers::new(),
             sync_handler,
+            server_error_policy,
+            errors,
+            active_connection: RwLock::new(None),
+            subscriptions: AsyncMutex::new(()),
+            connect_attempts: AtomicUsize::new(0),
+            endpoint_rotation: EndpointRotation::new(),
+            first_connect_made: AtomicBool::new(false),
+            connected_at: Mutex::new(None),
+        }
+    }
+
+    // Returns None for the very first connect and the number of the reconnect attempt
+    // made since the last stable connection otherwise
+    pub fn next_connect_attempt(&self) -> Option<usize> {
+        if !self.first_connect_made.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        Some(self.connect_attempts.fetch_add(1, Ordering::SeqCst))
+    }
+
+    // Connection is stable once the server sends the table or the connection stays up long enough.
+    // A server which accepts and drops the connection at once keeps the backoff growing
+    fn connection_is_stable(&self) {
+        self.connect_attempts.store(0, Ordering::SeqCst);
+        self.endpoint_rotation.connection_is_stable();
+    }
+
+    // Compression is not negotiated: Greeting of my-no-sql-tcp-shared 0.2.0 has no capabilities
+    // field, and GreetingFromNode, the only contract with the compress flag, would register
+    // the reader as a node. It needs a my-no-sql-tcp-shared release whose Greeting carries
+    // capabilities. Every CompressedPayload the server sends is unpacked in handle_incoming_packet.
+    fn create_greeting(&self) -> MyNoSqlTcpContract {
+        MyNoSqlTcpContract::Greeting {
+            name: self.app_name.to_string(),
+        }
+    }
+
+    pub async fn is_connected(&self) -> bool {
+        let read_access = self.active_connection.read().await;
+        read_access.is_some()
+    }
+
+    // Reader is registered and subscribed under the connection lock, so Connected either
+    // subscribes it together with the other readers or does not see it at all
+    pub async fn subscribe_reader<
+        TMyNoSqlEntity: MyNoSqlEntity + Sync + Send + DeserializeOwned + 'static,
+    >(
+        &self,
+        app_states: Arc<dyn ApplicationStates + Send + Sync + 'static>,
+    ) -> Result<Arc<MyNoSqlDataReaderTcp<TMyNoSqlEntity>>, GetReaderError> {
+        let _subscriptions = self.subscriptions.lock().await;
+        let active_connection = self.active_connection.read().await;
+
+        let (reader, created) = self
+            .subscribers
+            .create_subscriber(app_states, self.sync_handler.clone())
+            .await?;
+
+        if created {
+            if let Some(connection) = active_connection.as_ref() {
+                let contract = MyNoSqlTcpContract::Subscribe {
+                    table_name: TMyNoSqlEntity::TABLE_NAME.to_string(),
+                };
+                connection.send(contract).await;
+                reader.subscribed().await;
+            }
         }
+
+        Ok(reader)
     }
+
+    pub async fn unsubscribe_reader(&self, table_name: &str) -> RemoveReaderResult {
+        let _subscriptions = self.subscriptions.lock().await;
+
+        let update_event = match self.subscribers.remove(table_name).await {
+            RemovedSubscriber::Last(update_event) => update_event,
+            RemovedSubscriber::StillHeld(holders) => {
+                return RemoveReaderResult::StillHeld { holders }
+            }
+            RemovedSubscriber::NotFound => return RemoveReaderResult::NotFound,
+        };
+
+        {
+            let active_connection = self.active_connection.read().await;
+
+            if let Some(connection) = active_connection.as_ref() {
+                let contract = MyNoSqlTcpContract::Unsubscribe(table_name.to_string());
+                connection.send(contract).await;
+            }
+        }
+
+        update_event.unsubscribed().await;
+
+        RemoveReaderResult::Unsubscribed
+    }
+
+    pub fn subscribe_to_errors(&self) -> watch::Receiver<Option<MyNoSqlReaderError>> {
+        self.errors.subscribe()
+    }
+
+    async fn handle_error(&self, err: MyNoSqlReaderError, connection: &TcpConnection) {
+        self.errors.send_replace(Some(err.clone()));
+
+        match self.server_error_policy {
+            ServerErrorPolicy::LogAndReconnect => {
+                my_logger::LOGGER.write_error(
+                    "MyNoSqlTcpReader".to_string(),
+                    err.get_message(),
+                    None,
+                );
+                connection.disconnect().await;
+            }
+            ServerErrorPolicy::Propagate => {}
+            ServerErrorPolicy::Abort => {
+                panic!("{}", err.get_message());
+            }
+        }
+    }
+
     pub async fn handle_incoming_packet(
         &self,
         tcp_contract: MyNoSqlTcpContract,
-        _connection: Arc<TcpConnection>,
+        connection: Arc<TcpConnection>,
     ) {
+        let tcp_contract = match tcp_contract.decompress_if_compressed().await {
+            Ok(tcp_contract) => tcp_contract,
+            Err(message) => {
+                self.handle_error(
+                    MyNoSqlReaderError::CompressedPayload { message },
+                    connection.as_ref(),
+                )
+                .await;
+                return;
+            }
+        };
+
         match tcp_contract {
             MyNoSqlTcpContract::Ping => {}
             MyNoSqlTcpContract::Pong => {}
             MyNoSqlTcpContract::Greeting { name: _ } => {}
             MyNoSqlTcpContract::Subscribe { table_name: _ } => {}
             MyNoSqlTcpContract::InitTable { table_name, data } => {
+                self.connection_is_stable();
+
                 if let Some(update_event) = self.subscribers.get(table_name.as_str()).await {
                     update_event.as_ref().init_table(data).await;
                 }
@@ -60,7 +227,11 @@ impl TcpEvents {
                 }
             }
             MyNoSqlTcpContract::Error { message } => {
-                panic!("Server error: {}", message);
+                self.handle_error(
+                    MyNoSqlReaderError::ServerError { message },
+                    connection.as_ref(),
+                )
+                .await;
             }
             MyNoSqlTcpContract::GreetingFromNode {
                 node_location: _,
@@ -69,7 +240,14 @@ impl TcpEvents {
             } => {}
             MyNoSqlTcpContract::SubscribeAsNode(_) => {}
             MyNoSqlTcpContract::Unsubscribe(_) => {}
-            MyNoSqlTcpContract::TableNotFound(_) => {}
+            MyNoSqlTcpContract::TableNotFound(table_name) => {
+                if let Some(update_event) = self.subscribers.get(table_name.as_str()).await {
+                    update_event.as_ref().table_not_found().await;
+                }
+
+                self.errors
+                    .send_replace(Some(MyNoSqlReaderError::TableNotFound { table_name }));
+            }
             MyNoSqlTcpContract::CompressedPayload(_) => {}
             MyNoSqlTcpContract::Confirmation { confirmation_id } => self
                 .sync_handler
@@ -109,24 +287,43 @@ impl SocketEventCallback<MyNoSqlTcpContract, MyNoSqlReaderTcpSerializer> for Tcp
     ) {
         match connection_event {
             ConnectionEvent::Connected(connection) => {
-                let contract = MyNoSqlTcpContract::Greeting {
-                    name: self.app_name.to_string(),
-                };
+                connection.send(self.create_greeting()).await;
 
-                connection.send(contract).await;
+                let mut active_connection = self.active_connection.write().await;
 
-                for table in self.subscribers.get_tables_to_subscribe().await {
-                    let contract = MyNoSqlTcpContract::Subscribe {
-                        table_name: table.to_string(),
-                    };
+                for (table_name, update_event) in self.subscribers.get_all().await {
+                    let contract = MyNoSqlTcpContract::Subscribe { table_name };
 
                     connection.send(contract).await;
+                    update_event.subscribed().await;
                 }
 
+                *active_connection = Some(connection.clone());
+                drop(active_connection);
+
+                *self.connected_at.lock().unwrap() = Some(Instant::now());
+
                 self.sync_handler
                     .tcp_events_pusher_new_connection_established(connection);
             }
             ConnectionEvent::Disconnected(connection) => {
+                {
+                    let mut active_connection = self.active_connection.write().await;
+                    *active_connection = None;
+                }
+
+                let connected_at = self.connected_at.lock().unwrap().take();
+
+                if let Some(connected_at) = connected_at {
+                    if connected_at.elapsed() >= STABLE_CONNECTION_DURATION {
+                        self.connection_is_stable();
+                    }
+                }
+
+                for (_, update_event) in self.subscribers.get_all().await {
+                    update_event.disconnected().await;
+                }
+
                 self.sync_handler
                     .tcp_events_pusher_connection_disconnected(connection);
             }